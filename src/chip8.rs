@@ -1,16 +1,40 @@
+#[allow(clippy::module_inception)]
 mod chip8;
-pub use self::chip8::Chip8;
+pub use self::chip8::{
+    Chip8, DEFAULT_INSTRUCTIONS_PER_FRAME, DEFAULT_LOAD_ADDRESS, ETI_660_LOAD_ADDRESS,
+};
+pub use self::error::Chip8Error;
+pub use self::execution_state::ExecutionState;
+pub use self::frame_buffer::{FrameBuffer, PLANE_COUNT};
 pub use self::input_key::InputKey;
-pub use self::io::InputInstruction;
+pub use self::io::{Audio, Display, DrawInstruction, Headless, InputInstruction, Keypad};
+pub use self::memory::{Memory, EXTENDED_MEMORY_SIZE, MEMORY_SIZE};
+pub use self::op_code::OpCode;
+pub use self::platform::Platform;
+pub use self::quirks::Quirks;
+pub use self::registers::{Registers, VRegister};
+pub use self::sound::{Sound, PATTERN_LENGTH};
+pub use self::watchpoint::{MemoryAccess, Watchpoint, WatchpointHit};
 
+mod error;
+mod execution_state;
 mod frame_buffer;
 mod input_key;
 mod io;
 mod memory;
 mod op_code;
+mod platform;
+mod quirks;
 mod registers;
+mod rewind;
+mod rpl_flags;
+mod save_state;
+mod sound;
+mod timers;
+mod watchpoint;
 
-use self::frame_buffer::{FrameBuffer, FrameBufferMemory};
-use self::memory::Memory;
-use self::op_code::OpCode;
-use self::registers::{all_registers, Registers};
+use self::registers::all_registers;
+use self::rewind::RewindBuffer;
+use self::rpl_flags::RplFlags;
+use self::save_state::{Snapshot, StateReader, StateWriter};
+use self::timers::Timers;
@@ -1,16 +1,23 @@
 use rand::prelude::*;
-use std::{
-    convert::TryFrom,
-    sync::mpsc::{Receiver, Sender},
-};
+use rand_chacha::ChaCha8Rng;
+use std::{convert::TryFrom, io, path::PathBuf};
 
 use super::{
-    all_registers,
-    io::{DrawInstruction, InputInstruction},
-    registers::VRegister,
-    FrameBuffer, InputKey, Memory, OpCode, Registers,
+    all_registers, registers::VRegister, save_state, Audio, Chip8Error, Display, ExecutionState,
+    FrameBuffer, InputInstruction, InputKey, Keypad, Memory, OpCode, Platform, Quirks, Registers,
+    RewindBuffer, RplFlags, Snapshot, Sound, StateReader, StateWriter, Timers, Watchpoint,
+    PATTERN_LENGTH,
 };
 
+/// Where programs start in memory on most interpreters
+pub const DEFAULT_LOAD_ADDRESS: u16 = 0x200;
+
+/// Where programs start in memory on the ETI-660
+pub const ETI_660_LOAD_ADDRESS: u16 = 0x600;
+
+/// Roughly the speed of the original COSMAC VIP interpreter
+pub const DEFAULT_INSTRUCTIONS_PER_FRAME: usize = 11;
+
 pub struct Chip8 {
     memory: Memory,
     index: u16,
@@ -19,119 +26,885 @@ pub struct Chip8 {
     stack: [u16; 16],
     frame_buffer: FrameBuffer,
     v_registers: Registers,
-    display_tx: Sender<DrawInstruction>,
-    input_rx: Receiver<InputInstruction>,
+    timers: Timers,
+    rpl_flags: RplFlags,
+    state: ExecutionState,
+    quirks: Quirks,
+    platform: Platform,
+    waiting_for_vblank: bool,
+    load_address: u16,
+    /// Hash of the loaded ROM, identifying the program save states belong to
+    rom_hash: u64,
+    instructions_per_frame: usize,
+    /// Instructions executed so far in the current frame
+    frame_instructions: usize,
+    /// States of the latest frames, when rewinding is enabled
+    rewind_buffer: Option<RewindBuffer>,
+    watchpoints: Vec<Watchpoint>,
+    /// One bit per key currently held down
+    keys: u16,
+    /// One bit per key pressed since `Fx0A` started waiting
+    new_key_presses: u16,
+    /// Key pressed while waiting on `Fx0A`, which completes once it is released
+    awaited_key: Option<u8>,
+    rng: ChaCha8Rng,
+    /// Whether the host gave the generator a seed, which it then restarts from on every load
+    seeded: bool,
+    sound: Sound,
+    display: Box<dyn Display + Send>,
+    keypad: Box<dyn Keypad + Send>,
+    audio: Box<dyn Audio + Send>,
 }
 
 impl Chip8 {
-    pub fn new(display_tx: Sender<DrawInstruction>, input_rx: Receiver<InputInstruction>) -> Chip8 {
+    /// Creates a machine that shows its screen on `display`, reads keys from `keypad` and plays
+    /// sound through `audio`. `Headless` can stand in for any of them.
+    pub fn new(
+        display: impl Display + Send + 'static,
+        keypad: impl Keypad + Send + 'static,
+        audio: impl Audio + Send + 'static,
+        quirks: Quirks,
+    ) -> Chip8 {
         Chip8 {
-            memory: Memory::default(),
+            memory: Memory::new(Platform::default().memory_size()),
             index: 0,
-            program_counter: 0x200,
+            program_counter: DEFAULT_LOAD_ADDRESS,
             stack_pointer: 0,
             stack: [0; 16],
             frame_buffer: FrameBuffer::default(),
             v_registers: Registers::default(),
-            display_tx,
-            input_rx,
+            timers: Timers::default(),
+            rpl_flags: RplFlags::default(),
+            state: ExecutionState::Running,
+            quirks,
+            platform: Platform::default(),
+            waiting_for_vblank: false,
+            load_address: DEFAULT_LOAD_ADDRESS,
+            rom_hash: save_state::fnv1a(&[]),
+            rewind_buffer: None,
+            watchpoints: Vec::new(),
+            instructions_per_frame: DEFAULT_INSTRUCTIONS_PER_FRAME,
+            frame_instructions: 0,
+            keys: 0,
+            new_key_presses: 0,
+            awaited_key: None,
+            rng: ChaCha8Rng::from_entropy(),
+            seeded: false,
+            sound: Sound::default(),
+            display: Box::new(display),
+            keypad: Box::new(keypad),
+            audio: Box::new(audio),
         }
     }
 
-    pub fn load(&mut self, rom: impl IntoIterator<Item = u8>) {
+    /// Sets where `load` puts programs and starts executing them
+    pub fn set_load_address(&mut self, address: u16) {
+        self.load_address = address;
+    }
+
+    /// Sets the machine `load` sets up for the program, CHIP-8 unless told otherwise
+    pub fn set_platform(&mut self, platform: Platform) {
+        self.platform = platform;
+    }
+
+    /// Resets the machine and loads a program at the load address, ready to run
+    ///
+    /// Everything but the quirks, the platform, the host backends and the RPL user flags starts
+    /// over, so the same instance can run several programs one after the other. A seeded random
+    /// number generator restarts from its seed, an unseeded one gets a fresh one.
+    pub fn load(&mut self, rom: impl IntoIterator<Item = u8>) -> Result<(), Chip8Error> {
+        let rom: Vec<u8> = rom.into_iter().collect();
+        let capacity = self
+            .platform
+            .memory_size()
+            .saturating_sub(self.load_address as usize);
+
+        if rom.len() > capacity {
+            return Err(Chip8Error::RomTooLarge {
+                size: rom.len(),
+                capacity,
+            });
+        }
+
+        self.reset();
+        self.rom_hash = save_state::fnv1a(&rom);
+
         for (offset, byte) in rom.into_iter().enumerate() {
-            self.memory.set(0x200 + (offset as u16), byte);
+            self.memory.set(self.load_address + offset as u16, byte)?;
+        }
+
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.memory = Memory::new(self.platform.memory_size());
+        self.index = 0;
+        self.program_counter = self.load_address;
+        self.stack_pointer = 0;
+        self.stack = [0; 16];
+        self.frame_buffer = FrameBuffer::default();
+        self.v_registers = Registers::default();
+        self.apply_watchpoints();
+        self.timers = Timers::default();
+        self.state = ExecutionState::Running;
+        self.waiting_for_vblank = false;
+        self.frame_instructions = 0;
+        self.new_key_presses = 0;
+        self.awaited_key = None;
+        self.rng = if self.seeded {
+            ChaCha8Rng::from_seed(self.rng.get_seed())
+        } else {
+            ChaCha8Rng::from_entropy()
+        };
+        self.sound = Sound::default();
+        self.audio.update(&self.sound);
+
+        if let Some(rewind_buffer) = &mut self.rewind_buffer {
+            rewind_buffer.clear();
         }
     }
 
-    pub fn run(&mut self) {
-        while self.run_instruction(None) {}
+    /// Serializes the whole machine, so that `load_state` can later bring it back to this point
+    ///
+    /// The configuration set on the instance, such as the quirks, the platform and the number of
+    /// instructions per frame, is not part of the state. Neither are the RPL user flags, which live
+    /// outside of the machine.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::default();
+        self.write_state(&mut writer);
+        writer.into_bytes()
     }
 
-    pub fn run_instruction(&mut self, current_input: Option<InputKey>) -> bool {
-        let mut rng = thread_rng();
+    fn write_state(&self, writer: &mut StateWriter) {
+        writer.bytes(save_state::MAGIC);
+        writer.u16(save_state::VERSION);
+        writer.u64(self.rom_hash);
 
-        match self.next_opcode() {
-            Some(op_code) => {
-                trace!("Op: {:?}", op_code);
+        self.memory.save(writer);
+        writer.u16(self.index);
+        writer.u16(self.program_counter);
+        writer.u8(self.stack_pointer);
+        for address in &self.stack {
+            writer.u16(*address);
+        }
+        self.v_registers.save(writer);
+        self.timers.save(writer);
+        self.frame_buffer.save(writer);
+        self.state.save(writer);
+        writer.bool(self.waiting_for_vblank);
+        writer.u16(self.new_key_presses);
+        writer.u8(self.awaited_key.unwrap_or(u8::MAX));
+        writer.bytes(&self.rng.get_seed());
+        writer.u64(self.rng.get_stream());
+        writer.u128(self.rng.get_word_pos());
+        self.sound.save(writer);
+    }
 
-                match op_code {
-                    OpCode::Cls => self.frame_buffer.clear(),
-                    OpCode::Jump(location) => self.program_counter = location,
-                    OpCode::Random(register, k) => {
-                        self.v_registers.set(register, k & rng.gen_range(0..=0xFF))
-                    }
-                    OpCode::LoadIndex(value) => self.index = value,
-                    OpCode::LoadDecimal(register) => {
-                        let value = self.v_registers.get(register);
+    /// Restores a state produced by `save_state` while running the same ROM
+    ///
+    /// The machine is left untouched when the state is rejected.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), Chip8Error> {
+        let mut reader = StateReader::new(state);
 
-                        let hundreds = value / 100;
-                        let tens = value / 10 % 10;
-                        let ones = value % 10;
+        if reader.bytes(save_state::MAGIC.len())? != save_state::MAGIC {
+            return Err(Chip8Error::InvalidSaveState);
+        }
 
-                        self.memory.set(self.index, hundreds);
-                        self.memory.set(self.index + 1, tens);
-                        self.memory.set(self.index + 2, ones);
-                    }
-                    OpCode::Fill(end) => {
-                        for register in all_registers().take_while(|register| register <= &end) {
-                            self.v_registers.set(register, self.memory.get(self.index));
-                            self.index += 1;
-                        }
-                    }
-                    OpCode::LoadCharacter(register) => {
-                        let value = self.v_registers.get(register);
-                        self.index = self.memory.index_of_char(value);
-                    }
-                    OpCode::SetRegister(register, value) => self.v_registers.set(register, value),
-                    OpCode::Draw((x, y), length) => {
-                        let x = self.v_registers.get(x);
-                        let y = self.v_registers.get(y);
-                        let sprite = self.memory.range(self.index..self.index + length as u16);
-                        let turned_bit_off = self.frame_buffer.draw((x, y), sprite);
-
-                        if turned_bit_off {
-                            self.v_registers.set(VRegister::VF, 1);
-                        } else {
-                            self.v_registers.set(VRegister::VF, 0);
-                        }
-
-                        self.display_tx
-                            .send(DrawInstruction::new(self.frame_buffer.clone()))
-                            .unwrap();
+        let version = reader.u16()?;
+        if version != save_state::VERSION {
+            return Err(Chip8Error::UnsupportedSaveStateVersion { version });
+        }
+
+        if reader.u64()? != self.rom_hash {
+            return Err(Chip8Error::SaveStateRomMismatch);
+        }
+
+        let memory = Memory::restore(&mut reader)?;
+        let index = reader.u16()?;
+        let program_counter = reader.u16()?;
+        let stack_pointer = reader.u8()?;
+        let mut stack = [0; 16];
+        for address in &mut stack {
+            *address = reader.u16()?;
+        }
+        let v_registers = Registers::restore(&mut reader)?;
+        let timers = Timers::restore(&mut reader)?;
+        let frame_buffer = FrameBuffer::restore(&mut reader)?;
+        let state = ExecutionState::restore(&mut reader)?;
+        let waiting_for_vblank = reader.bool()?;
+        let new_key_presses = reader.u16()?;
+        let awaited_key = match reader.u8()? {
+            u8::MAX => None,
+            key if key < 16 => Some(key),
+            _ => return Err(Chip8Error::InvalidSaveState),
+        };
+        let mut rng = ChaCha8Rng::from_seed(reader.array()?);
+        rng.set_stream(reader.u64()?);
+        rng.set_word_pos(reader.u128()?);
+        let sound = Sound::restore(&mut reader)?;
+        reader.finish()?;
+
+        if memory.size() != self.platform.memory_size() || stack_pointer as usize > stack.len() {
+            return Err(Chip8Error::InvalidSaveState);
+        }
+
+        self.memory = memory;
+        self.index = index;
+        self.program_counter = program_counter;
+        self.stack_pointer = stack_pointer;
+        self.stack = stack;
+        self.v_registers = v_registers;
+        self.apply_watchpoints();
+        self.timers = timers;
+        self.frame_buffer = frame_buffer;
+        self.state = state;
+        self.waiting_for_vblank = waiting_for_vblank;
+        self.frame_instructions = 0;
+        self.new_key_presses = new_key_presses;
+        self.awaited_key = awaited_key;
+        self.rng = rng;
+        self.sound = sound;
+        self.sound.set_active(self.timers.is_sound_active());
+        self.audio.update(&self.sound);
+        self.display.draw(&self.frame_buffer);
+
+        Ok(())
+    }
+
+    /// Keeps the RPL user flags in the given file, so they survive between runs of the same ROM
+    pub fn set_rpl_flags_path(&mut self, path: impl Into<PathBuf>) {
+        self.rpl_flags = RplFlags::persistent(path);
+    }
+
+    /// Writes the RPL user flags to their file if they changed since the last call
+    ///
+    /// They are also written when the machine is dropped, but errors can only be logged then.
+    pub fn save_rpl_flags(&mut self) -> io::Result<()> {
+        self.rpl_flags.flush()
+    }
+
+    /// Seeds the generator used by `Cxkk`, so that runs with the same seed and input are identical
+    pub fn with_seed(self, seed: u64) -> Chip8 {
+        self.with_rng(ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    /// Uses the given generator for `Cxkk`, restarting it from its seed on every load
+    pub fn with_rng(mut self, rng: ChaCha8Rng) -> Chip8 {
+        self.rng = rng;
+        self.seeded = true;
+        self
+    }
+
+    /// Sets how many instructions `run_frame` executes, which determines the speed of programs
+    ///
+    /// Panics if it is zero, as programs would then never make progress.
+    pub fn set_instructions_per_frame(&mut self, instructions_per_frame: usize) {
+        assert!(instructions_per_frame > 0, "no instructions per frame");
+        self.instructions_per_frame = instructions_per_frame;
+    }
+
+    /// Runs frames until the loaded program halts, faults or hits a watchpoint
+    ///
+    /// Frames run as fast as possible, hosts showing the program call `run_frame` at 60 Hz instead.
+    pub fn run(&mut self) -> Result<(), Chip8Error> {
+        while self.state.is_active() {
+            self.run_frame()?;
+        }
+
+        Ok(())
+    }
+
+    /// Keeps the state of up to `frames` previous frames, so that `rewind` can go back to them.
+    /// Zero disables rewinding.
+    pub fn set_rewind_capacity(&mut self, frames: usize) {
+        self.rewind_buffer = if frames == 0 {
+            None
+        } else {
+            Some(RewindBuffer::new(frames))
+        };
+    }
+
+    /// Brings the machine back to how it was up to `frames` calls to `run_frame` ago, returning how
+    /// many frames it actually went back, which is limited by the recorded history
+    pub fn rewind(&mut self, frames: usize) -> Result<usize, Chip8Error> {
+        let (state, rewound) = match &mut self.rewind_buffer {
+            Some(buffer) => match buffer.rewind(frames)? {
+                Some((state, rewound)) => (state.to_vec(), rewound),
+                None => return Ok(0),
+            },
+            None => return Ok(0),
+        };
+
+        self.load_state(&state)?;
+        Ok(rewound)
+    }
+
+    /// Number of frames `rewind` can currently go back
+    pub fn rewindable_frames(&self) -> usize {
+        self.rewind_buffer.as_ref().map_or(0, RewindBuffer::frames)
+    }
+
+    /// Stops execution after any instruction meeting the condition, leaving the machine in the
+    /// `ExecutionState::Watchpoint` state until `resume` is called. Watchpoints are kept when
+    /// loading a ROM or a save state.
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.watchpoints.push(watchpoint);
+        self.apply_watchpoints();
+    }
+
+    pub fn clear_watchpoints(&mut self) {
+        self.watchpoints.clear();
+        self.apply_watchpoints();
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    /// Continues execution after a watchpoint stopped it
+    pub fn resume(&mut self) {
+        if let ExecutionState::Watchpoint { .. } = self.state {
+            self.state = ExecutionState::Running;
+        }
+    }
+
+    fn apply_watchpoints(&mut self) {
+        self.memory.set_watchpoints(&self.watchpoints);
+        self.v_registers.set_watchpoints(&self.watchpoints);
+    }
+
+    /// Executes one 60th of a second worth of instructions and ticks the timers once
+    ///
+    /// The speed of the program only depends on the number of instructions per frame, so calling
+    /// this at 60 Hz runs it the same on any host. Returns the screen as left by the frame.
+    pub fn run_frame(&mut self) -> Result<&FrameBuffer, Chip8Error> {
+        while !self.step()? {}
+
+        Ok(&self.frame_buffer)
+    }
+
+    /// Executes the next instruction of the current frame, then ends the frame if it was the last
+    /// one or the program now waits for the display interrupt. Returns whether the frame ended.
+    ///
+    /// Stepping until the frame ends is the same as `run_frame`, which lets debuggers stop anywhere
+    /// in a frame. A machine that is not running ends the frame without executing anything.
+    pub fn step(&mut self) -> Result<bool, Chip8Error> {
+        if self.frame_instructions == 0 {
+            while let Some(instruction) = self.keypad.poll() {
+                match instruction {
+                    InputInstruction::Press(key) => self.key_down(key),
+                    InputInstruction::Release(key) => self.key_up(key),
+                }
+            }
+        }
+
+        if self.is_frame_running() {
+            self.frame_instructions += 1;
+            self.run_instruction()?;
+        }
+
+        if self.is_frame_running() {
+            return Ok(false);
+        }
+
+        self.tick_timers();
+        self.frame_instructions = 0;
+
+        if let Some(mut rewind_buffer) = self.rewind_buffer.take() {
+            rewind_buffer.record(|writer| self.write_state(writer));
+            self.rewind_buffer = Some(rewind_buffer);
+        }
+
+        Ok(true)
+    }
+
+    /// Whether the current frame has instructions left to execute
+    fn is_frame_running(&self) -> bool {
+        self.frame_instructions < self.instructions_per_frame
+            && self.state.is_active()
+            && !self.waiting_for_vblank
+    }
+
+    /// Executes a single instruction
+    ///
+    /// Faults in the running program are returned as errors instead of panicking, so a host can
+    /// report them and keep running. A faulted or halted machine does nothing when asked to run
+    /// further instructions.
+    pub fn run_instruction(&mut self) -> Result<(), Chip8Error> {
+        if !self.state.is_active() || self.waiting_for_vblank {
+            return Ok(());
+        }
+
+        // Only accesses made by the instruction count, not the ones made by the host in between
+        let address = self.program_counter;
+        self.memory.take_watchpoint_hit();
+        self.v_registers.take_watchpoint_hit();
+
+        if let Err(error) = self.execute() {
+            self.state = ExecutionState::Faulted(error.clone());
+            return Err(error);
+        }
+
+        let hit = self
+            .memory
+            .take_watchpoint_hit()
+            .or_else(|| self.v_registers.take_watchpoint_hit());
+
+        if let Some(hit) = hit {
+            self.state = ExecutionState::Watchpoint { address, hit };
+        }
+
+        Ok(())
+    }
+
+    fn execute(&mut self) -> Result<(), Chip8Error> {
+        let address = self.program_counter;
+        let op_code = self.next_opcode()?;
+        trace!("Op: {:?}", op_code);
+
+        match op_code {
+            OpCode::Cls => self.frame_buffer.clear(),
+            OpCode::ScrollDown(rows) => self.frame_buffer.scroll_down(rows as usize),
+            OpCode::ScrollRight => self.frame_buffer.scroll_right(4),
+            OpCode::ScrollLeft => self.frame_buffer.scroll_left(4),
+            OpCode::LowResolution => self.frame_buffer.set_high_resolution(false),
+            OpCode::HighResolution => self.frame_buffer.set_high_resolution(true),
+            OpCode::Jump(location) => {
+                // Jumping to itself is the idiomatic way for a program to stop
+                if location == address {
+                    self.state = ExecutionState::Halted;
+                }
+
+                self.program_counter = location;
+            }
+            OpCode::JumpOffset(location) => {
+                let register = if self.quirks.jump_with_vx {
+                    VRegister::try_from((location >> 8) as u8).unwrap()
+                } else {
+                    VRegister::V0
+                };
+
+                self.program_counter = location + self.v_registers.get(register) as u16
+            }
+            OpCode::Call(location) => {
+                if self.stack_pointer as usize >= self.stack.len() {
+                    return Err(Chip8Error::StackOverflow { address });
+                }
+
+                self.stack[self.stack_pointer as usize] = self.program_counter;
+                self.stack_pointer += 1;
+                self.program_counter = location;
+            }
+            OpCode::Return => {
+                if self.stack_pointer == 0 {
+                    return Err(Chip8Error::StackUnderflow { address });
+                }
+
+                self.stack_pointer -= 1;
+                self.program_counter = self.stack[self.stack_pointer as usize];
+            }
+            OpCode::Random(register, k) => self.v_registers.set(register, k & self.rng.gen::<u8>()),
+            OpCode::SkipIfEqual(register, value) => {
+                if self.v_registers.get(register) == value {
+                    self.skip_instruction();
+                }
+            }
+            OpCode::SkipIfNotEqual(register, value) => {
+                if self.v_registers.get(register) != value {
+                    self.skip_instruction();
+                }
+            }
+            OpCode::SkipIfRegistersEqual(x, y) => {
+                if self.v_registers.get(x) == self.v_registers.get(y) {
+                    self.skip_instruction();
+                }
+            }
+            OpCode::SkipIfRegistersNotEqual(x, y) => {
+                if self.v_registers.get(x) != self.v_registers.get(y) {
+                    self.skip_instruction();
+                }
+            }
+            OpCode::SkipIfKeyPressed(register) => {
+                if self.is_key_pressed(register) {
+                    self.skip_instruction();
+                }
+            }
+            OpCode::SkipIfKeyNotPressed(register) => {
+                if !self.is_key_pressed(register) {
+                    self.skip_instruction();
+                }
+            }
+            OpCode::LoadIndex(value) => self.index = value,
+            OpCode::LoadDecimal(register) => {
+                let value = self.v_registers.get(register);
+
+                let hundreds = value / 100;
+                let tens = value / 10 % 10;
+                let ones = value % 10;
+
+                self.memory.set(self.index, hundreds)?;
+                self.memory.set(self.index.wrapping_add(1), tens)?;
+                self.memory.set(self.index.wrapping_add(2), ones)?;
+            }
+            OpCode::Store(end) => {
+                let start = self.index;
+
+                for register in all_registers().take_while(|register| register <= &end) {
+                    self.memory
+                        .set(self.index, self.v_registers.get(register))?;
+                    self.index = self.index.wrapping_add(1);
+                }
+
+                if !self.quirks.load_store_increments_index {
+                    self.index = start;
+                }
+            }
+            OpCode::Fill(end) => {
+                let start = self.index;
+
+                for register in all_registers().take_while(|register| register <= &end) {
+                    self.v_registers.set(register, self.memory.get(self.index)?);
+                    self.index = self.index.wrapping_add(1);
+                }
+
+                if !self.quirks.load_store_increments_index {
+                    self.index = start;
+                }
+            }
+            OpCode::LoadDelayTimer(register) => self.v_registers.set(register, self.timers.delay()),
+            OpCode::SetDelayTimer(register) => {
+                self.timers.set_delay(self.v_registers.get(register))
+            }
+            OpCode::SetSoundTimer(register) => {
+                self.timers.set_sound(self.v_registers.get(register));
+                self.update_sound_flag();
+            }
+            OpCode::AddIndex(register) => {
+                self.index = self
+                    .index
+                    .wrapping_add(self.v_registers.get(register) as u16)
+            }
+            OpCode::LoadCharacter(register) => {
+                let value = self.v_registers.get(register);
+                self.index = self.memory.index_of_char(value)?;
+            }
+            OpCode::LoadLargeCharacter(register) => {
+                let value = self.v_registers.get(register);
+                self.index = self.memory.index_of_large_char(value)?;
+            }
+            OpCode::SaveFlags(end) => {
+                let values: Vec<u8> = all_registers()
+                    .take_while(|register| register <= &end)
+                    .map(|register| self.v_registers.get(register))
+                    .collect();
+                self.rpl_flags.save(&values);
+            }
+            OpCode::LoadFlags(end) => {
+                let registers = all_registers().take_while(|register| register <= &end);
+                let count = usize::from(end) + 1;
+
+                for (register, value) in registers.zip(self.rpl_flags.load(count)) {
+                    self.v_registers.set(register, *value);
+                }
+            }
+            OpCode::SaveRange(x, y) => {
+                for (offset, register) in register_range(x, y).enumerate() {
+                    let address = self.index.wrapping_add(offset as u16);
+                    self.memory.set(address, self.v_registers.get(register))?;
+                }
+            }
+            OpCode::LoadRange(x, y) => {
+                for (offset, register) in register_range(x, y).enumerate() {
+                    let address = self.index.wrapping_add(offset as u16);
+                    self.v_registers.set(register, self.memory.get(address)?);
+                }
+            }
+            OpCode::LoadAudioPattern => {
+                // `range` returns exactly the requested length
+                let pattern = self.memory.range(self.index, PATTERN_LENGTH)?;
+                self.sound
+                    .set_pattern(<&[u8; PATTERN_LENGTH]>::try_from(pattern).unwrap());
+                self.audio.update(&self.sound);
+            }
+            OpCode::SetPitch(register) => {
+                self.sound.set_pitch(self.v_registers.get(register));
+                self.audio.update(&self.sound);
+            }
+            OpCode::SelectPlanes(planes) => self.frame_buffer.select_planes(planes),
+            OpCode::LoadLongIndex => {
+                let most_significant_byte = self.memory.peek(self.program_counter)?;
+                let least_significant_byte =
+                    self.memory.peek(self.program_counter.wrapping_add(1))?;
+                self.index = u16::from_be_bytes([most_significant_byte, least_significant_byte]);
+                self.program_counter = self.program_counter.wrapping_add(2);
+            }
+            OpCode::SetRegister(register, value) => self.v_registers.set(register, value),
+            OpCode::AddValue(register, value) => {
+                let current = self.v_registers.get(register);
+                self.v_registers.set(register, current.wrapping_add(value));
+            }
+            OpCode::CopyRegister(x, y) => {
+                self.v_registers.set(x, self.v_registers.get(y));
+            }
+            OpCode::Or(x, y) => {
+                let value = self.v_registers.get(x) | self.v_registers.get(y);
+                self.v_registers.set(x, value);
+                self.reset_flag_after_logic();
+            }
+            OpCode::And(x, y) => {
+                let value = self.v_registers.get(x) & self.v_registers.get(y);
+                self.v_registers.set(x, value);
+                self.reset_flag_after_logic();
+            }
+            OpCode::Xor(x, y) => {
+                let value = self.v_registers.get(x) ^ self.v_registers.get(y);
+                self.v_registers.set(x, value);
+                self.reset_flag_after_logic();
+            }
+            OpCode::Add(x, y) => {
+                let (value, carry) = self
+                    .v_registers
+                    .get(x)
+                    .overflowing_add(self.v_registers.get(y));
+                // VF is written last so it holds the flag even when it is the target
+                self.v_registers.set(x, value);
+                self.v_registers.set(VRegister::VF, carry as u8);
+            }
+            OpCode::Sub(x, y) => {
+                let (value, borrow) = self
+                    .v_registers
+                    .get(x)
+                    .overflowing_sub(self.v_registers.get(y));
+                self.v_registers.set(x, value);
+                self.v_registers.set(VRegister::VF, !borrow as u8);
+            }
+            OpCode::SubNegated(x, y) => {
+                let (value, borrow) = self
+                    .v_registers
+                    .get(y)
+                    .overflowing_sub(self.v_registers.get(x));
+                self.v_registers.set(x, value);
+                self.v_registers.set(VRegister::VF, !borrow as u8);
+            }
+            OpCode::ShiftRight(x, y) => {
+                let value = self.v_registers.get(self.shift_source(x, y));
+                self.v_registers.set(x, value >> 1);
+                self.v_registers.set(VRegister::VF, value & 0x01);
+            }
+            OpCode::ShiftLeft(x, y) => {
+                let value = self.v_registers.get(self.shift_source(x, y));
+                self.v_registers.set(x, value << 1);
+                self.v_registers.set(VRegister::VF, value >> 7);
+            }
+            OpCode::Draw((x, y), length) => {
+                let x = self.v_registers.get(x);
+                let y = self.v_registers.get(y);
+                let clip = self.quirks.clip_sprites;
+
+                let turned_bit_off = if length == 0 {
+                    let length = 32 * self.frame_buffer.selected_plane_count();
+                    let sprite = self.memory.range(self.index, length)?;
+                    self.frame_buffer.draw_large((x, y), sprite, clip)
+                } else {
+                    let length = length as usize * self.frame_buffer.selected_plane_count();
+                    let sprite = self.memory.range(self.index, length)?;
+                    self.frame_buffer.draw((x, y), sprite, clip)
+                };
+
+                if turned_bit_off {
+                    self.v_registers.set(VRegister::VF, 1);
+                } else {
+                    self.v_registers.set(VRegister::VF, 0);
+                }
+
+                self.display.draw(&self.frame_buffer);
+
+                if self.quirks.display_wait {
+                    self.waiting_for_vblank = true;
+                }
+            }
+            OpCode::WaitForKeyPress(register) => {
+                // Keys already held down when the wait starts don't count, as on the COSMAC VIP
+                if self.state != ExecutionState::WaitingForKey {
+                    self.new_key_presses = 0;
+                    self.awaited_key = None;
+                }
+
+                if self.awaited_key.is_none() && self.new_key_presses != 0 {
+                    self.awaited_key = Some(self.new_key_presses.trailing_zeros() as u8);
+                }
+
+                match self.awaited_key {
+                    Some(key) if self.keys & (1 << key) == 0 => {
+                        self.v_registers.set(register, key);
+                        self.awaited_key = None;
+                        self.state = ExecutionState::Running;
                     }
-                    OpCode::WaitForKeyPress(register) => {
-                        let instruction = self.input_rx.recv().unwrap();
-                        let input: InputKey = instruction.into();
-                        self.v_registers.set(register, input.into());
+                    _ => {
+                        // Execute this same instruction again until a key is pressed and released
+                        self.program_counter = address;
+                        self.state = ExecutionState::WaitingForKey;
                     }
                 }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn next_opcode(&mut self) -> Result<OpCode, Chip8Error> {
+        let address = self.program_counter;
+        let out_of_range = |_| Chip8Error::ProgramCounterOutOfRange { address };
+
+        let most_significant_byte = self.memory.peek(address).map_err(out_of_range)?;
+        let least_significant_byte = self
+            .memory
+            .peek(address.wrapping_add(1))
+            .map_err(out_of_range)?;
+
+        let bytes = [most_significant_byte, least_significant_byte];
+        let invalid = || Chip8Error::InvalidOpCode {
+            address,
+            op_code: u16::from_be_bytes(bytes),
+        };
+        let op_code = OpCode::try_from(bytes).map_err(|_| invalid())?;
 
-                true
+        if !self.is_supported(&op_code) {
+            return Err(invalid());
+        }
+
+        self.program_counter = self.program_counter.wrapping_add(2);
+        Ok(op_code)
+    }
+
+    /// Whether the platform has the instruction, beyond it being decodable
+    fn is_supported(&self, op_code: &OpCode) -> bool {
+        match *op_code {
+            OpCode::SaveFlags(end) | OpCode::LoadFlags(end) => {
+                usize::from(end) < self.platform.rpl_flag_count()
             }
-            None => false,
+            OpCode::SaveRange(..)
+            | OpCode::LoadRange(..)
+            | OpCode::LoadLongIndex
+            | OpCode::SelectPlanes(_)
+            | OpCode::LoadAudioPattern
+            | OpCode::SetPitch(_) => self.platform == Platform::XoChip,
+            _ => true,
+        }
+    }
+
+    /// Decrements the delay and sound timers. Meant to be called at 60 Hz.
+    pub fn tick_timers(&mut self) {
+        self.waiting_for_vblank = false;
+        self.timers.tick();
+        self.update_sound_flag();
+    }
+
+    fn update_sound_flag(&mut self) {
+        let active = self.timers.is_sound_active();
+
+        if active != self.sound.is_active() {
+            self.sound.set_active(active);
+            self.audio.update(&self.sound);
         }
     }
 
-    fn next_opcode(&mut self) -> Option<OpCode> {
-        let most_significant_byte = self.memory.get(self.program_counter);
-        self.program_counter += 1;
-        let least_significant_byte = self.memory.get(self.program_counter);
-        self.program_counter += 1;
+    fn shift_source(&self, x: VRegister, y: VRegister) -> VRegister {
+        if self.quirks.shift_uses_vy {
+            y
+        } else {
+            x
+        }
+    }
 
-        if most_significant_byte == 0 && least_significant_byte == 0 {
-            // Placeholder for now
-            return None;
+    fn reset_flag_after_logic(&mut self) {
+        if self.quirks.logic_resets_vf {
+            self.v_registers.set(VRegister::VF, 0);
         }
+    }
+
+    /// Skips over the next instruction, taking into account that `F000 nnnn` is 4 bytes long
+    fn skip_instruction(&mut self) {
+        let next = (
+            self.memory.peek(self.program_counter),
+            self.memory.peek(self.program_counter.wrapping_add(1)),
+        );
+
+        // `F000 nnnn` is the only 4 byte instruction, and only XO-CHIP has it
+        let long = self.platform == Platform::XoChip && next == (Ok(0xF0), Ok(0x00));
+        let length = if long { 4 } else { 2 };
+        self.program_counter = self.program_counter.wrapping_add(length);
+    }
 
-        // TODO: Review how to crash on an invalid program
-        Some(OpCode::try_from([most_significant_byte, least_significant_byte]).unwrap())
+    fn is_key_pressed(&self, register: VRegister) -> bool {
+        let key = self.v_registers.get(register) & 0x0F;
+        self.keys & (1 << key) != 0
+    }
+
+    /// Marks the key as held down, until a matching `key_up`
+    pub fn key_down(&mut self, key: InputKey) {
+        let bit = 1 << u8::from(key);
+        self.keys |= bit;
+        self.new_key_presses |= bit;
+    }
+
+    pub fn key_up(&mut self, key: InputKey) {
+        self.keys &= !(1 << u8::from(key));
+    }
+
+    pub fn is_key_down(&self, key: InputKey) -> bool {
+        self.keys & (1 << u8::from(key)) != 0
+    }
+
+    pub fn state(&self) -> &ExecutionState {
+        &self.state
     }
 
     pub fn frame_buffer(&self) -> &FrameBuffer {
         &self.frame_buffer
     }
 
+    pub fn registers(&self) -> &Registers {
+        &self.v_registers
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    pub fn program_counter(&self) -> u16 {
+        self.program_counter
+    }
+
+    /// Return addresses of the subroutines currently being executed, innermost last
+    pub fn stack(&self) -> &[u16] {
+        &self.stack[..self.stack_pointer as usize]
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.timers.delay()
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.timers.sound()
+    }
+
+    pub fn sound(&self) -> &Sound {
+        &self.sound
+    }
+
+    pub fn quirks(&self) -> &Quirks {
+        &self.quirks
+    }
+
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
     pub fn get_screen_width(&self) -> usize {
         self.frame_buffer().get_screen_width()
     }
@@ -141,51 +914,81 @@ impl Chip8 {
     }
 }
 
+/// Registers from `first` to `last`, both included, going backwards if `first` comes after `last`
+fn register_range(first: VRegister, last: VRegister) -> impl Iterator<Item = VRegister> {
+    let mut registers: Vec<VRegister> = all_registers()
+        .filter(|register| {
+            (first <= *register && *register <= last) || (last <= *register && *register <= first)
+        })
+        .collect();
+
+    if first > last {
+        registers.reverse();
+    }
+
+    registers.into_iter()
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::chip8::registers::VRegister;
+    use crate::chip8::{
+        save_state, Headless, MemoryAccess, VRegister, Watchpoint, WatchpointHit,
+        ETI_660_LOAD_ADDRESS,
+    };
 
     use super::*;
 
     fn build_chip() -> Chip8 {
-        use std::sync::mpsc::channel;
+        build_chip_with_quirks(Quirks::COSMAC_VIP)
+    }
+
+    fn build_chip_with_quirks(quirks: Quirks) -> Chip8 {
+        Chip8::new(Headless, Headless, Headless, quirks)
+    }
 
-        let (tx, _) = channel();
-        let (_, rx) = channel();
+    fn build_xo_chip() -> Chip8 {
+        let mut chip8 = build_chip_with_quirks(Quirks::XO_CHIP);
+        chip8.set_platform(Platform::XoChip);
+        chip8
+    }
 
-        Chip8::new(tx, rx)
+    /// Appends a jump to itself at the end of the program, so that running it halts
+    fn with_halt(mut program: Vec<u8>) -> Vec<u8> {
+        let halt_address = 0x200 + program.len() as u16;
+        program.extend_from_slice(&(0x1000 | halt_address).to_be_bytes());
+        program
     }
 
     #[test]
     fn test_load_index() {
         let mut chip8 = build_chip();
-        chip8.load(vec![0xA1, 0x23].into_iter());
-        chip8.run();
+        chip8.load(with_halt(vec![0xA1, 0x23])).unwrap();
+        chip8.run().unwrap();
         assert_eq!(chip8.index, 0x0123);
     }
 
     #[test]
     fn test_load_binary() {
         let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0xA0, 0xFB, 0xF2, 0x33])).unwrap();
         chip8.v_registers.set(VRegister::V2, 234);
-        chip8.load(vec![0xA0, 0xFB, 0xF2, 0x33].into_iter());
-        chip8.run();
-        assert_eq!(chip8.memory.get(0x0FB), 2);
-        assert_eq!(chip8.memory.get(0x0FC), 3);
-        assert_eq!(chip8.memory.get(0x0FD), 4);
+        chip8.run().unwrap();
+        assert_eq!(chip8.memory.get(0x0FB).unwrap(), 2);
+        assert_eq!(chip8.memory.get(0x0FC).unwrap(), 3);
+        assert_eq!(chip8.memory.get(0x0FD).unwrap(), 4);
     }
 
     #[test]
     fn test_fill_registers() {
         let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0xF2, 0x65])).unwrap();
         chip8.index = 0x300;
-        chip8.memory.set(0x300, 12);
-        chip8.memory.set(0x301, 34);
-        chip8.memory.set(0x302, 56);
-        chip8.memory.set(0x303, 78);
+        chip8.memory.set(0x300, 12).unwrap();
+        chip8.memory.set(0x301, 34).unwrap();
+        chip8.memory.set(0x302, 56).unwrap();
+        chip8.memory.set(0x303, 78).unwrap();
 
-        chip8.load(vec![0xF2, 0x65].into_iter());
-        chip8.run();
+        chip8.run().unwrap();
 
         assert_eq!(chip8.index, 0x303);
         assert_eq!(chip8.v_registers.get(VRegister::V0), 12);
@@ -194,12 +997,50 @@ mod tests {
         assert_eq!(chip8.v_registers.get(VRegister::V3), 0);
     }
 
+    #[test]
+    fn test_store_registers() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0xF2, 0x55])).unwrap();
+        chip8.index = 0x300;
+        chip8.v_registers.set(VRegister::V0, 12);
+        chip8.v_registers.set(VRegister::V1, 34);
+        chip8.v_registers.set(VRegister::V2, 56);
+        chip8.v_registers.set(VRegister::V3, 78);
+
+        chip8.run().unwrap();
+
+        assert_eq!(chip8.index, 0x303);
+        assert_eq!(chip8.memory.get(0x300).unwrap(), 12);
+        assert_eq!(chip8.memory.get(0x301).unwrap(), 34);
+        assert_eq!(chip8.memory.get(0x302).unwrap(), 56);
+        assert_eq!(chip8.memory.get(0x303).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_add_index() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0xF4, 0x1E])).unwrap();
+        chip8.index = 0x300;
+        chip8.v_registers.set(VRegister::V4, 0x12);
+        chip8.run().unwrap();
+        assert_eq!(chip8.index, 0x312);
+    }
+
+    #[test]
+    fn test_jump_offset() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0xB3, 0x00]).unwrap();
+        chip8.v_registers.set(VRegister::V0, 0x04);
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.program_counter, 0x304);
+    }
+
     #[test]
     fn test_load_character() {
         let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0xF2, 0x29])).unwrap();
         chip8.v_registers.set(VRegister::V2, 0xB);
-        chip8.load(vec![0xF2, 0x29].into_iter());
-        chip8.run();
+        chip8.run().unwrap();
         // This test is tightly coupled to the memory addresses currently in use for the font
         assert_eq!(chip8.index, 55);
     }
@@ -207,8 +1048,1215 @@ mod tests {
     #[test]
     fn test_set_register() {
         let mut chip8 = build_chip();
-        chip8.load(vec![0x62, 0x29].into_iter());
-        chip8.run();
+        chip8.load(with_halt(vec![0x62, 0x29])).unwrap();
+        chip8.run().unwrap();
         assert_eq!(chip8.v_registers.get(VRegister::V2), 0x29);
     }
+
+    #[test]
+    fn test_add_value_wraps_without_touching_vf() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0x71, 0x02])).unwrap();
+        chip8.v_registers.set(VRegister::V1, 0xFF);
+        chip8.v_registers.set(VRegister::VF, 0x42);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 0x01);
+        assert_eq!(chip8.v_registers.get(VRegister::VF), 0x42);
+    }
+
+    #[test]
+    fn test_copy_register() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0x81, 0x20])).unwrap();
+        chip8.v_registers.set(VRegister::V2, 0x12);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 0x12);
+    }
+
+    #[test]
+    fn test_bitwise_operations() {
+        let mut chip8 = build_chip();
+        chip8
+            .load(with_halt(vec![0x81, 0x41, 0x82, 0x42, 0x83, 0x43]))
+            .unwrap();
+        chip8.v_registers.set(VRegister::V1, 0b1100);
+        chip8.v_registers.set(VRegister::V2, 0b1100);
+        chip8.v_registers.set(VRegister::V3, 0b1100);
+        chip8.v_registers.set(VRegister::V4, 0b1010);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 0b1110);
+        assert_eq!(chip8.v_registers.get(VRegister::V2), 0b1000);
+        assert_eq!(chip8.v_registers.get(VRegister::V3), 0b0110);
+    }
+
+    #[test]
+    fn test_add_with_carry() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0x81, 0x24])).unwrap();
+        chip8.v_registers.set(VRegister::V1, 0xF0);
+        chip8.v_registers.set(VRegister::V2, 0x20);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 0x10);
+        assert_eq!(chip8.v_registers.get(VRegister::VF), 1);
+    }
+
+    #[test]
+    fn test_add_without_carry() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0x81, 0x24])).unwrap();
+        chip8.v_registers.set(VRegister::V1, 0x10);
+        chip8.v_registers.set(VRegister::V2, 0x20);
+        chip8.v_registers.set(VRegister::VF, 1);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 0x30);
+        assert_eq!(chip8.v_registers.get(VRegister::VF), 0);
+    }
+
+    #[test]
+    fn test_add_into_vf_keeps_flag() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0x8F, 0x14])).unwrap();
+        chip8.v_registers.set(VRegister::VF, 0xFF);
+        chip8.v_registers.set(VRegister::V1, 0x02);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::VF), 1);
+    }
+
+    #[test]
+    fn test_sub_with_borrow() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0x81, 0x25])).unwrap();
+        chip8.v_registers.set(VRegister::V1, 0x10);
+        chip8.v_registers.set(VRegister::V2, 0x20);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 0xF0);
+        assert_eq!(chip8.v_registers.get(VRegister::VF), 0);
+    }
+
+    #[test]
+    fn test_sub_without_borrow() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0x81, 0x25])).unwrap();
+        chip8.v_registers.set(VRegister::V1, 0x20);
+        chip8.v_registers.set(VRegister::V2, 0x20);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 0x00);
+        assert_eq!(chip8.v_registers.get(VRegister::VF), 1);
+    }
+
+    #[test]
+    fn test_sub_negated() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0x81, 0x27])).unwrap();
+        chip8.v_registers.set(VRegister::V1, 0x10);
+        chip8.v_registers.set(VRegister::V2, 0x30);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 0x20);
+        assert_eq!(chip8.v_registers.get(VRegister::VF), 1);
+    }
+
+    #[test]
+    fn test_shift_right() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0x81, 0x26])).unwrap();
+        chip8.v_registers.set(VRegister::V2, 0b0000_0101);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 0b0000_0010);
+        assert_eq!(chip8.v_registers.get(VRegister::VF), 1);
+    }
+
+    #[test]
+    fn test_shift_left() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0x81, 0x2E])).unwrap();
+        chip8.v_registers.set(VRegister::V2, 0b0100_0001);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 0b1000_0010);
+        assert_eq!(chip8.v_registers.get(VRegister::VF), 0);
+    }
+
+    #[test]
+    fn test_call_and_return() {
+        let mut chip8 = build_chip();
+        chip8
+            .load(with_halt(vec![
+                0x22, 0x06, 0x61, 0x11, 0x12, 0x04, 0x62, 0x22, 0x00, 0xEE,
+            ]))
+            .unwrap();
+        // 0x200: call 0x206; 0x202: set V1; 0x204: halt; 0x206: set V2, return
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 0x11);
+        assert_eq!(chip8.v_registers.get(VRegister::V2), 0x22);
+        assert_eq!(chip8.stack_pointer, 0);
+    }
+
+    #[test]
+    fn test_call_pushes_return_address() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0x23, 0x00]).unwrap();
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.program_counter, 0x300);
+        assert_eq!(chip8.stack_pointer, 1);
+        assert_eq!(chip8.stack[0], 0x202);
+    }
+
+    #[test]
+    fn test_stack_overflow() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0x22, 0x00]).unwrap();
+        // Calls itself forever
+        assert_eq!(
+            chip8.run(),
+            Err(Chip8Error::StackOverflow { address: 0x200 })
+        );
+        assert_eq!(chip8.stack_pointer, 16);
+    }
+
+    #[test]
+    fn test_stack_underflow() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0x00, 0xEE]).unwrap();
+        assert_eq!(
+            chip8.run(),
+            Err(Chip8Error::StackUnderflow { address: 0x200 })
+        );
+    }
+
+    #[test]
+    fn test_skip_if_equal() {
+        let mut chip8 = build_chip();
+        chip8
+            .load(with_halt(vec![0x31, 0x12, 0x62, 0x01, 0x63, 0x01]))
+            .unwrap();
+        chip8.v_registers.set(VRegister::V1, 0x12);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V2), 0);
+        assert_eq!(chip8.v_registers.get(VRegister::V3), 1);
+    }
+
+    #[test]
+    fn test_skip_if_not_equal() {
+        let mut chip8 = build_chip();
+        chip8
+            .load(with_halt(vec![
+                0x41, 0x12, 0x62, 0x01, 0x41, 0x13, 0x63, 0x01,
+            ]))
+            .unwrap();
+        chip8.v_registers.set(VRegister::V1, 0x12);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V2), 1);
+        assert_eq!(chip8.v_registers.get(VRegister::V3), 0);
+    }
+
+    #[test]
+    fn test_skip_if_registers_equal() {
+        let mut chip8 = build_chip();
+        chip8
+            .load(with_halt(vec![
+                0x51, 0x20, 0x63, 0x01, 0x91, 0x20, 0x64, 0x01,
+            ]))
+            .unwrap();
+        chip8.v_registers.set(VRegister::V1, 0x12);
+        chip8.v_registers.set(VRegister::V2, 0x12);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V3), 0);
+        assert_eq!(chip8.v_registers.get(VRegister::V4), 1);
+    }
+
+    #[test]
+    fn test_skip_if_key_pressed() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0xE1, 0x9E, 0xE1, 0x9E]).unwrap();
+        chip8.v_registers.set(VRegister::V1, 0xA);
+
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.program_counter, 0x202);
+
+        chip8.key_down(InputKey::KeyA);
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.program_counter, 0x206);
+    }
+
+    #[test]
+    fn test_skip_if_key_not_pressed() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0xE1, 0xA1, 0xE1, 0xA1]).unwrap();
+        chip8.v_registers.set(VRegister::V1, 0xA);
+
+        chip8.key_down(InputKey::KeyA);
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.program_counter, 0x202);
+
+        chip8.key_up(InputKey::KeyA);
+        chip8.key_down(InputKey::KeyB);
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.program_counter, 0x206);
+    }
+
+    #[test]
+    fn test_delay_timer() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0xF1, 0x15, 0xF2, 0x07]).unwrap();
+        chip8.v_registers.set(VRegister::V1, 10);
+        chip8.run_instruction().unwrap();
+        chip8.tick_timers();
+        chip8.tick_timers();
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V2), 8);
+    }
+
+    #[test]
+    fn test_timers_do_not_tick_per_instruction() {
+        let mut chip8 = build_chip();
+        chip8
+            .load(vec![0xF1, 0x15, 0x62, 0x00, 0x62, 0x00, 0xF2, 0x07])
+            .unwrap();
+        chip8.v_registers.set(VRegister::V1, 10);
+        chip8.run_instruction().unwrap();
+        chip8.run_instruction().unwrap();
+        chip8.run_instruction().unwrap();
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V2), 10);
+    }
+
+    #[test]
+    fn test_sound_timer() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0xF1, 0x18]).unwrap();
+        chip8.v_registers.set(VRegister::V1, 1);
+        assert!(!chip8.sound.is_active());
+        chip8.run_instruction().unwrap();
+        assert!(chip8.sound.is_active());
+        chip8.tick_timers();
+        assert!(!chip8.sound.is_active());
+    }
+
+    #[test]
+    fn test_audio_pattern_and_pitch() {
+        let mut chip8 = build_xo_chip();
+        chip8
+            .load(vec![0xF0, 0x02, 0xF1, 0x3A, 0xF2, 0x18])
+            .unwrap();
+        chip8.index = 0x300;
+        chip8.memory.set(0x300, 0b01000000).unwrap();
+        chip8.v_registers.set(VRegister::V1, 112);
+        chip8.v_registers.set(VRegister::V2, 1);
+        for _ in 0..3 {
+            chip8.run_instruction().unwrap();
+        }
+
+        let sound = &mut chip8.sound;
+        assert_eq!(sound.playback_rate(), 8000.0);
+        let mut samples = [0.0; 2];
+        sound.generate(4000, &mut samples);
+        // Twice the default rate skips over the only set bit
+        assert!(samples.iter().all(|sample| *sample < 0.0));
+    }
+
+    #[test]
+    fn test_invalid_opcode() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0x62, 0x01, 0xFF, 0xFF]).unwrap();
+        assert_eq!(
+            chip8.run(),
+            Err(Chip8Error::InvalidOpCode {
+                address: 0x202,
+                op_code: 0xFFFF
+            })
+        );
+        assert_eq!(chip8.program_counter, 0x202);
+    }
+
+    #[test]
+    fn test_program_counter_out_of_range() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0x1F, 0xFF]).unwrap();
+        assert_eq!(
+            chip8.run(),
+            Err(Chip8Error::ProgramCounterOutOfRange { address: 0xFFF })
+        );
+    }
+
+    #[test]
+    fn test_invalid_font_digit() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0xF2, 0x29]).unwrap();
+        chip8.v_registers.set(VRegister::V2, 0x10);
+        assert_eq!(
+            chip8.run(),
+            Err(Chip8Error::InvalidFontDigit { digit: 0x10 })
+        );
+    }
+
+    #[test]
+    fn test_store_out_of_bounds() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0xF1, 0x55]).unwrap();
+        chip8.index = 0xFFF;
+        assert_eq!(
+            chip8.run(),
+            Err(Chip8Error::MemoryOutOfBounds { address: 0x1000 })
+        );
+    }
+
+    #[test]
+    fn test_halts_on_jump_to_itself() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0x62, 0x01, 0x12, 0x02]).unwrap();
+        chip8.run().unwrap();
+        assert_eq!(chip8.state(), &ExecutionState::Halted);
+        assert_eq!(chip8.program_counter, 0x202);
+
+        // Further instructions are ignored
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.program_counter, 0x202);
+    }
+
+    #[test]
+    fn test_zeroed_memory_faults() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0x62, 0x01]).unwrap();
+        assert!(chip8.run().is_err());
+        assert_eq!(
+            chip8.state(),
+            &ExecutionState::Faulted(Chip8Error::InvalidOpCode {
+                address: 0x202,
+                op_code: 0x0000
+            })
+        );
+    }
+
+    #[test]
+    fn test_wait_for_key_press() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0xF3, 0x0A]).unwrap();
+
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.state(), &ExecutionState::WaitingForKey);
+        assert_eq!(chip8.program_counter, 0x200);
+
+        // Nothing happens until the key is released
+        chip8.key_down(InputKey::Key7);
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.state(), &ExecutionState::WaitingForKey);
+        assert_eq!(chip8.program_counter, 0x200);
+
+        chip8.key_up(InputKey::Key7);
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.state(), &ExecutionState::Running);
+        assert_eq!(chip8.program_counter, 0x202);
+        assert_eq!(chip8.v_registers.get(VRegister::V3), 0x7);
+    }
+
+    #[test]
+    fn test_wait_for_key_press_ignores_held_keys() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0xF3, 0x0A]).unwrap();
+        chip8.key_down(InputKey::Key1);
+
+        chip8.run_instruction().unwrap();
+        chip8.key_up(InputKey::Key1);
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.state(), &ExecutionState::WaitingForKey);
+
+        chip8.key_down(InputKey::Key2);
+        chip8.key_up(InputKey::Key2);
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V3), 0x2);
+    }
+
+    #[test]
+    fn test_timers_run_while_waiting_for_key() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0xF0, 0x15, 0xF3, 0x0A]).unwrap();
+        chip8.v_registers.set(VRegister::V0, 5);
+
+        chip8.run_frame().unwrap();
+        chip8.run_frame().unwrap();
+        assert_eq!(chip8.state(), &ExecutionState::WaitingForKey);
+        assert_eq!(chip8.delay_timer(), 3);
+    }
+
+    #[test]
+    fn test_shift_in_place_quirk() {
+        let mut chip8 = build_chip_with_quirks(Quirks::SCHIP);
+        chip8.load(with_halt(vec![0x81, 0x26])).unwrap();
+        chip8.v_registers.set(VRegister::V1, 0b0000_0101);
+        chip8.v_registers.set(VRegister::V2, 0b1000_0000);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 0b0000_0010);
+        assert_eq!(chip8.v_registers.get(VRegister::VF), 1);
+    }
+
+    #[test]
+    fn test_load_store_without_incrementing_index() {
+        let mut chip8 = build_chip_with_quirks(Quirks::SCHIP);
+        chip8.load(with_halt(vec![0xF2, 0x55, 0xF2, 0x65])).unwrap();
+        chip8.index = 0x300;
+        chip8.run().unwrap();
+        assert_eq!(chip8.index, 0x300);
+    }
+
+    #[test]
+    fn test_jump_with_vx_quirk() {
+        let mut chip8 = build_chip_with_quirks(Quirks::SCHIP);
+        chip8.load(vec![0xB3, 0x00]).unwrap();
+        chip8.v_registers.set(VRegister::V0, 0x01);
+        chip8.v_registers.set(VRegister::V3, 0x04);
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.program_counter, 0x304);
+    }
+
+    #[test]
+    fn test_logic_resets_vf_quirk() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0x81, 0x21])).unwrap();
+        chip8.v_registers.set(VRegister::VF, 1);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::VF), 0);
+
+        let mut chip8 = build_chip_with_quirks(Quirks::XO_CHIP);
+        chip8.load(with_halt(vec![0x81, 0x21])).unwrap();
+        chip8.v_registers.set(VRegister::VF, 1);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::VF), 1);
+    }
+
+    #[test]
+    fn test_display_wait_quirk() {
+        let mut chip8 = build_chip_with_quirks(Quirks::COSMAC_VIP);
+        chip8.load(vec![0xD0, 0x01, 0x61, 0x01]).unwrap();
+
+        chip8.run_instruction().unwrap();
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.program_counter, 0x202);
+
+        chip8.tick_timers();
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 1);
+    }
+
+    #[test]
+    fn test_switch_resolution() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![0x00, 0xFF, 0x00, 0xFE]).unwrap();
+
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.get_screen_width(), 128);
+        assert_eq!(chip8.get_screen_height(), 64);
+
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.get_screen_width(), 64);
+        assert_eq!(chip8.get_screen_height(), 32);
+    }
+
+    #[test]
+    fn test_draw_large_sprite() {
+        let mut chip8 = build_chip_with_quirks(Quirks::SCHIP);
+        chip8.load(vec![0x00, 0xFF, 0xD0, 0x00]).unwrap();
+        chip8.index = 0x300;
+        for offset in 0..32 {
+            chip8.memory.set(0x300 + offset, 0xFF).unwrap();
+        }
+        chip8.run_instruction().unwrap();
+        chip8.run_instruction().unwrap();
+
+        let buffer = chip8.frame_buffer();
+        for y in 0..64 {
+            for x in 0..128 {
+                assert_eq!(buffer.pixel(x, y), (x < 16 && y < 16) as u8);
+            }
+        }
+    }
+
+    #[test]
+    fn test_scroll() {
+        let mut chip8 = build_chip_with_quirks(Quirks::SCHIP);
+        chip8
+            .load(vec![0x00, 0xC3, 0x00, 0xFB, 0x00, 0xFC, 0x00, 0xFC])
+            .unwrap();
+        chip8.frame_buffer.draw((8, 0), &[0b11111111], false);
+        for _ in 0..4 {
+            chip8.run_instruction().unwrap();
+        }
+        let buffer = chip8.frame_buffer();
+        assert!((0..64).all(|x| buffer.pixel(x, 0) == 0));
+        assert!((0..64).all(|x| buffer.pixel(x, 3) == (4..12).contains(&x) as u8));
+    }
+
+    #[test]
+    fn test_load_large_character() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0xF2, 0x30])).unwrap();
+        chip8.v_registers.set(VRegister::V2, 0x3);
+        chip8.run().unwrap();
+        // This test is tightly coupled to the memory addresses currently in use for the font
+        assert_eq!(chip8.index, 110);
+    }
+
+    #[test]
+    fn test_save_and_load_flags() {
+        let mut chip8 = build_chip();
+        chip8
+            .load(with_halt(vec![
+                0xF1, 0x75, 0x60, 0x00, 0x61, 0x00, 0xF2, 0x85,
+            ]))
+            .unwrap();
+        chip8.v_registers.set(VRegister::V0, 12);
+        chip8.v_registers.set(VRegister::V1, 34);
+        chip8.v_registers.set(VRegister::V2, 56);
+        chip8.run().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V0), 12);
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 34);
+        assert_eq!(chip8.v_registers.get(VRegister::V2), 0);
+    }
+
+    #[test]
+    fn test_flag_count() {
+        // SUPER-CHIP only has 8 flags
+        let mut chip8 = build_chip();
+        chip8.load(vec![0xF8, 0x75]).unwrap();
+        assert_eq!(
+            chip8.run_instruction(),
+            Err(Chip8Error::InvalidOpCode {
+                address: 0x200,
+                op_code: 0xF875
+            })
+        );
+
+        let mut chip8 = build_xo_chip();
+        chip8.load(vec![0xFF, 0x75]).unwrap();
+        assert_eq!(chip8.run_instruction(), Ok(()));
+    }
+
+    #[test]
+    fn test_extended_memory() {
+        let mut chip8 = build_xo_chip();
+        chip8.load(vec![]).unwrap();
+        assert!(chip8.memory.get(0xFFFF).is_ok());
+
+        // Octo's quirks for CHIP-8 programs keep 4 KiB of memory, only the platform extends it
+        let mut chip8 = build_chip_with_quirks(Quirks::OCTO);
+        chip8.load(vec![]).unwrap();
+        assert!(chip8.memory.get(0xFFFF).is_err());
+    }
+
+    #[test]
+    fn test_load_long_index() {
+        let mut chip8 = build_xo_chip();
+        chip8.load(vec![0xF0, 0x00, 0xAB, 0xCD]).unwrap();
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.index, 0xABCD);
+        assert_eq!(chip8.program_counter, 0x204);
+    }
+
+    #[test]
+    fn test_skip_over_long_index() {
+        let mut chip8 = build_xo_chip();
+        chip8
+            .load(vec![0x30, 0x00, 0xF0, 0x00, 0xAB, 0xCD])
+            .unwrap();
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.program_counter, 0x206);
+
+        // Other platforms have no 4 byte instructions to skip over
+        let mut chip8 = build_chip();
+        chip8
+            .load(vec![0x30, 0x00, 0xF0, 0x00, 0xAB, 0xCD])
+            .unwrap();
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.program_counter, 0x204);
+    }
+
+    #[test]
+    fn test_xo_chip_instructions_need_platform() {
+        let mut chip8 = build_chip_with_quirks(Quirks::XO_CHIP);
+        chip8.load(vec![0xF0, 0x00, 0xAB, 0xCD]).unwrap();
+        assert_eq!(
+            chip8.run_instruction(),
+            Err(Chip8Error::InvalidOpCode {
+                address: 0x200,
+                op_code: 0xF000
+            })
+        );
+    }
+
+    #[test]
+    fn test_execute_from_end_of_memory() {
+        let mut chip8 = build_xo_chip();
+        chip8.load(vec![]).unwrap();
+        chip8.memory.set(0xFFFE, 0x60).unwrap();
+        chip8.memory.set(0xFFFF, 0x05).unwrap();
+        chip8.program_counter = 0xFFFE;
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V0), 5);
+        assert_eq!(chip8.program_counter, 0);
+
+        let run_at_end = |chip8: &mut Chip8, op_code: [u8; 2]| {
+            chip8.memory.set(0xFFFE, op_code[0]).unwrap();
+            chip8.memory.set(0xFFFF, op_code[1]).unwrap();
+            chip8.program_counter = 0xFFFE;
+            chip8.run_instruction()
+        };
+
+        run_at_end(&mut chip8, [0x12, 0x00]).unwrap();
+        assert_eq!(chip8.program_counter, 0x200);
+        assert_eq!(chip8.state, ExecutionState::Running);
+
+        // The return address wraps around too
+        run_at_end(&mut chip8, [0x23, 0x00]).unwrap();
+        assert_eq!(chip8.program_counter, 0x300);
+        assert_eq!(chip8.stack(), &[0]);
+
+        run_at_end(&mut chip8, [0x00, 0xEE]).unwrap();
+        assert_eq!(chip8.program_counter, 0);
+        assert_eq!(
+            run_at_end(&mut chip8, [0x00, 0xEE]),
+            Err(Chip8Error::StackUnderflow { address: 0xFFFE })
+        );
+
+        let mut chip8 = build_xo_chip();
+        chip8.load(vec![]).unwrap();
+        run_at_end(&mut chip8, [0xF0, 0x0A]).unwrap();
+        assert_eq!(chip8.program_counter, 0xFFFE);
+        assert_eq!(chip8.state, ExecutionState::WaitingForKey);
+    }
+
+    #[test]
+    fn test_save_and_load_range() {
+        let mut chip8 = build_xo_chip();
+        chip8.load(with_halt(vec![0x52, 0x42, 0x57, 0x53])).unwrap();
+        chip8.index = 0x300;
+        chip8.v_registers.set(VRegister::V2, 12);
+        chip8.v_registers.set(VRegister::V3, 34);
+        chip8.v_registers.set(VRegister::V4, 56);
+        // save v2 - v4, load v7 - v5
+        chip8.run().unwrap();
+
+        assert_eq!(chip8.index, 0x300);
+        assert_eq!(chip8.memory.range(0x300, 3).unwrap(), &[12, 34, 56]);
+        assert_eq!(chip8.v_registers.get(VRegister::V7), 12);
+        assert_eq!(chip8.v_registers.get(VRegister::V6), 34);
+        assert_eq!(chip8.v_registers.get(VRegister::V5), 56);
+    }
+
+    #[test]
+    fn test_draw_on_two_planes() {
+        let mut chip8 = build_xo_chip();
+        chip8.load(vec![0xF3, 0x01, 0xD0, 0x01]).unwrap();
+        chip8.index = 0x300;
+        chip8.memory.set(0x300, 0b10000000).unwrap();
+        chip8.memory.set(0x301, 0b11000000).unwrap();
+        chip8.run_instruction().unwrap();
+        chip8.run_instruction().unwrap();
+
+        assert_eq!(chip8.frame_buffer().pixel(0, 0), 3);
+        assert_eq!(chip8.frame_buffer().pixel(1, 0), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_instructions_per_frame() {
+        build_chip().set_instructions_per_frame(0);
+    }
+
+    #[test]
+    fn test_run_frame() {
+        let mut chip8 = build_chip();
+        chip8
+            .load(with_halt(vec![
+                0xF0, 0x15, 0x71, 0x01, 0x71, 0x01, 0x71, 0x01, 0x71, 0x01,
+            ]))
+            .unwrap();
+        chip8.set_instructions_per_frame(3);
+        chip8.v_registers.set(VRegister::V0, 2);
+        // Set the delay timer, then keep adding 1 to V1
+
+        chip8.run_frame().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 2);
+        assert_eq!(chip8.delay_timer(), 1);
+
+        chip8.run_frame().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 4);
+        assert_eq!(chip8.delay_timer(), 0);
+    }
+
+    #[test]
+    fn test_step_through_frame() {
+        let mut chip8 = build_chip();
+        chip8
+            .load(with_halt(vec![0xF0, 0x15, 0x71, 0x01, 0x71, 0x01]))
+            .unwrap();
+        chip8.set_instructions_per_frame(2);
+        chip8.set_rewind_capacity(10);
+        chip8.v_registers.set(VRegister::V0, 2);
+
+        assert_eq!(chip8.step(), Ok(false));
+        assert_eq!(chip8.delay_timer(), 2);
+        assert_eq!(chip8.step(), Ok(true));
+        assert_eq!(chip8.delay_timer(), 1);
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 1);
+
+        // Frames ended by stepping can be rewound like the ones from `run_frame`
+        assert_eq!(chip8.step(), Ok(false));
+        assert_eq!(chip8.step(), Ok(true));
+        assert_eq!(chip8.rewind(1), Ok(1));
+        assert_eq!(chip8.program_counter, 0x204);
+    }
+
+    #[test]
+    fn test_run_frame_stops_at_display_wait() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0xD0, 0x01, 0x61, 0x01])).unwrap();
+        chip8.run_frame().unwrap();
+        assert_eq!(chip8.program_counter, 0x202);
+        chip8.run_frame().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 1);
+    }
+
+    #[test]
+    fn test_random_with_seed() {
+        let program = with_halt(vec![0xC0, 0xFF, 0xC1, 0xFF, 0xC2, 0xFF, 0xC3, 0xFF]);
+        let run_with_seed = |seed| {
+            let mut chip8 = build_chip().with_seed(seed);
+            chip8.load(program.clone()).unwrap();
+            chip8.run().unwrap();
+            (0..4)
+                .map(|register| {
+                    chip8
+                        .v_registers
+                        .get(VRegister::try_from(register).unwrap())
+                })
+                .collect::<Vec<u8>>()
+        };
+
+        assert_eq!(run_with_seed(1), run_with_seed(1));
+        assert_ne!(run_with_seed(1), run_with_seed(2));
+    }
+
+    #[test]
+    fn test_random_with_rng() {
+        let mut first = build_chip().with_rng(ChaCha8Rng::seed_from_u64(7));
+        first.load(vec![0xC0, 0xFF]).unwrap();
+        let mut second = build_chip().with_rng(ChaCha8Rng::seed_from_u64(7));
+        second.load(vec![0xC0, 0xFF]).unwrap();
+        first.run_instruction().unwrap();
+        second.run_instruction().unwrap();
+        assert_eq!(
+            first.v_registers.get(VRegister::V0),
+            second.v_registers.get(VRegister::V0)
+        );
+    }
+
+    #[test]
+    fn test_unseeded_random_differs_between_loads() {
+        let mut chip8 = build_chip();
+        chip8.load(vec![]).unwrap();
+        let seed = chip8.rng.get_seed();
+        chip8.load(vec![]).unwrap();
+        assert_ne!(chip8.rng.get_seed(), seed);
+    }
+
+    #[test]
+    fn test_run_frame_takes_keypad_events() {
+        use std::sync::mpsc::channel;
+
+        let (tx, rx) = channel();
+        let mut chip8 = Chip8::new(Headless, rx, Headless, Quirks::COSMAC_VIP);
+        chip8.load(with_halt(vec![])).unwrap();
+
+        tx.send(InputInstruction::Press(InputKey::Key4)).unwrap();
+        tx.send(InputInstruction::Press(InputKey::Key5)).unwrap();
+        tx.send(InputInstruction::Release(InputKey::Key4)).unwrap();
+        chip8.run_frame().unwrap();
+        assert!(!chip8.is_key_down(InputKey::Key4));
+        assert!(chip8.is_key_down(InputKey::Key5));
+    }
+
+    #[test]
+    fn test_load_rom_too_large() {
+        let mut chip8 = build_chip();
+        assert_eq!(
+            chip8.load(vec![0; 0xE01]),
+            Err(Chip8Error::RomTooLarge {
+                size: 0xE01,
+                capacity: 0xE00
+            })
+        );
+        assert!(chip8.load(vec![0; 0xE00]).is_ok());
+
+        let mut chip8 = build_xo_chip();
+        assert!(chip8.load(vec![0; 0xE01]).is_ok());
+    }
+
+    #[test]
+    fn test_load_address() {
+        let mut chip8 = build_chip();
+        chip8.set_load_address(ETI_660_LOAD_ADDRESS);
+        chip8.load(vec![0x61, 0x05]).unwrap();
+        assert_eq!(chip8.program_counter, 0x600);
+        assert_eq!(chip8.memory.get(0x600).unwrap(), 0x61);
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V1), 5);
+
+        assert_eq!(
+            chip8.load(vec![0; 0xA01]),
+            Err(Chip8Error::RomTooLarge {
+                size: 0xA01,
+                capacity: 0xA00
+            })
+        );
+    }
+
+    #[test]
+    fn test_load_resets_machine() {
+        let mut chip8 = build_chip_with_quirks(Quirks::SCHIP).with_seed(3);
+        chip8
+            .load(with_halt(vec![
+                0xC0, 0xFF, 0xD1, 0x15, 0x22, 0x08, 0x00, 0x00,
+            ]))
+            .unwrap();
+        for _ in 0..3 {
+            chip8.run_instruction().unwrap();
+        }
+        let random = chip8.v_registers.get(VRegister::V0);
+        assert_eq!(chip8.stack().len(), 1);
+        assert_eq!(chip8.frame_buffer().pixel(1, 0), 1);
+
+        chip8.load(vec![0xC0, 0xFF]).unwrap();
+        assert_eq!(chip8.program_counter, 0x200);
+        assert_eq!(chip8.index, 0);
+        assert!(chip8.stack().is_empty());
+        assert_eq!(chip8.memory.get(0x202).unwrap(), 0);
+        assert_eq!(chip8.frame_buffer().pixel(1, 0), 0);
+        assert_eq!(chip8.state(), &ExecutionState::Running);
+
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.v_registers.get(VRegister::V0), random);
+    }
+
+    /// Keeps drawing random sprites, calling a subroutine in between
+    fn random_sprites_program() -> Vec<u8> {
+        vec![
+            0xC0, 0x3F, 0xC1, 0x1F, 0xD0, 0x15, 0x22, 0x0A, 0x12, 0x00, 0xC2, 0xFF, 0x00, 0xEE,
+        ]
+    }
+
+    #[test]
+    fn test_save_state_round_trip() {
+        let mut chip8 = build_chip_with_quirks(Quirks::SCHIP).with_seed(5);
+        chip8.load(random_sprites_program()).unwrap();
+        for _ in 0..20 {
+            chip8.run_instruction().unwrap();
+        }
+
+        let state = chip8.save_state();
+        for _ in 0..20 {
+            chip8.run_instruction().unwrap();
+        }
+        let expected = chip8.save_state();
+
+        chip8.load_state(&state).unwrap();
+        assert_eq!(chip8.save_state(), state);
+        for _ in 0..20 {
+            chip8.run_instruction().unwrap();
+        }
+        assert_eq!(chip8.save_state(), expected);
+    }
+
+    #[test]
+    fn test_load_state_into_new_instance() {
+        let mut chip8 = build_chip_with_quirks(Quirks::SCHIP).with_seed(5);
+        chip8.load(random_sprites_program()).unwrap();
+        for _ in 0..7 {
+            chip8.run_instruction().unwrap();
+        }
+        let state = chip8.save_state();
+
+        let mut other = build_chip_with_quirks(Quirks::SCHIP);
+        other.load(random_sprites_program()).unwrap();
+        other.load_state(&state).unwrap();
+        assert_eq!(other.program_counter, chip8.program_counter);
+        assert_eq!(other.stack(), chip8.stack());
+        assert_eq!(
+            other.v_registers.get(VRegister::V0),
+            chip8.v_registers.get(VRegister::V0)
+        );
+
+        chip8.run_instruction().unwrap();
+        other.run_instruction().unwrap();
+        assert_eq!(
+            other.v_registers.get(VRegister::V2),
+            chip8.v_registers.get(VRegister::V2)
+        );
+    }
+
+    #[test]
+    fn test_load_state_keeps_host_playback_position() {
+        use std::sync::{Arc, Mutex};
+
+        let host = Arc::new(Mutex::new(Sound::default()));
+        let mut chip8 = Chip8::new(Headless, Headless, Arc::clone(&host), Quirks::XO_CHIP);
+        chip8.set_platform(Platform::XoChip);
+        chip8
+            .load(vec![0xF0, 0x02, 0xF1, 0x18, 0xF0, 0x02])
+            .unwrap();
+        chip8.index = 0x300;
+        chip8.memory.set(0x300, 0b10100000).unwrap();
+        chip8.v_registers.set(VRegister::V1, 10);
+        chip8.run_instruction().unwrap();
+        chip8.run_instruction().unwrap();
+
+        let mut samples = [0.0; 2];
+        host.lock().unwrap().generate(4000, &mut samples[..1]);
+        let state = chip8.save_state();
+
+        chip8.memory.set(0x300, 0xFF).unwrap();
+        chip8.run_instruction().unwrap();
+        chip8.load_state(&state).unwrap();
+
+        // The saved pattern is back, played on from where the host was rather than from its start
+        host.lock().unwrap().generate(4000, &mut samples);
+        assert!(samples[0] < 0.0 && samples[1] > 0.0);
+    }
+
+    #[test]
+    fn test_load_state_with_other_rom() {
+        let mut chip8 = build_chip();
+        chip8.load(random_sprites_program()).unwrap();
+        let state = chip8.save_state();
+
+        chip8.load(with_halt(vec![])).unwrap();
+        assert_eq!(
+            chip8.load_state(&state),
+            Err(Chip8Error::SaveStateRomMismatch)
+        );
+        assert_eq!(chip8.program_counter, 0x200);
+    }
+
+    #[test]
+    fn test_load_invalid_state() {
+        let mut chip8 = build_chip();
+        chip8.load(random_sprites_program()).unwrap();
+        let state = chip8.save_state();
+
+        assert_eq!(chip8.load_state(b"CH8"), Err(Chip8Error::InvalidSaveState));
+        assert_eq!(
+            chip8.load_state(&state[..state.len() - 1]),
+            Err(Chip8Error::InvalidSaveState)
+        );
+
+        let mut bad_magic = state.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(
+            chip8.load_state(&bad_magic),
+            Err(Chip8Error::InvalidSaveState)
+        );
+
+        let mut newer = state;
+        newer[4..6].copy_from_slice(&(save_state::VERSION + 1).to_le_bytes());
+        assert_eq!(
+            chip8.load_state(&newer),
+            Err(Chip8Error::UnsupportedSaveStateVersion {
+                version: save_state::VERSION + 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_rewind() {
+        let mut chip8 = build_chip_with_quirks(Quirks::SCHIP).with_seed(9);
+        chip8.set_rewind_capacity(30);
+        chip8.load(random_sprites_program()).unwrap();
+
+        let mut states = Vec::new();
+        for _ in 0..10 {
+            chip8.run_frame().unwrap();
+            states.push(chip8.save_state());
+        }
+
+        assert_eq!(chip8.rewindable_frames(), 9);
+        assert_eq!(chip8.rewind(3), Ok(3));
+        assert_eq!(chip8.save_state(), states[6]);
+
+        // Running again from there gives the same frames as the first time
+        chip8.run_frame().unwrap();
+        assert_eq!(chip8.save_state(), states[7]);
+
+        assert_eq!(chip8.rewind(100), Ok(7));
+        assert_eq!(chip8.save_state(), states[0]);
+    }
+
+    #[test]
+    fn test_rewind_disabled() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![])).unwrap();
+        chip8.run_frame().unwrap();
+        assert_eq!(chip8.rewind(1), Ok(0));
+    }
+
+    #[test]
+    fn test_rewind_history_cleared_on_load() {
+        let mut chip8 = build_chip();
+        chip8.set_rewind_capacity(30);
+        chip8.load(with_halt(vec![])).unwrap();
+        chip8.run_frame().unwrap();
+        chip8.run_frame().unwrap();
+        assert_eq!(chip8.rewindable_frames(), 1);
+
+        chip8.load(with_halt(vec![0x00, 0xE0])).unwrap();
+        assert_eq!(chip8.rewindable_frames(), 0);
+    }
+
+    #[test]
+    fn test_watch_load_decimal() {
+        let mut chip8 = build_chip();
+        chip8.add_watchpoint(Watchpoint::Memory {
+            range: 0x301..=0x301,
+            access: MemoryAccess::Write,
+        });
+        chip8.load(with_halt(vec![0x60, 0x7B, 0xF0, 0x33])).unwrap();
+        chip8.index = 0x300;
+
+        chip8.run().unwrap();
+
+        assert_eq!(
+            chip8.state(),
+            &ExecutionState::Watchpoint {
+                address: 0x202,
+                hit: WatchpointHit::MemoryWrite {
+                    address: 0x301,
+                    old: 0,
+                    new: 2
+                }
+            }
+        );
+        assert_eq!(chip8.program_counter(), 0x204);
+    }
+
+    #[test]
+    fn test_watch_store_registers() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0xF3, 0x55])).unwrap();
+        chip8.add_watchpoint(Watchpoint::Memory {
+            range: 0x302..=0x3FF,
+            access: MemoryAccess::ReadWrite,
+        });
+        chip8.index = 0x300;
+        chip8.v_registers.set(VRegister::V2, 56);
+
+        chip8.run_instruction().unwrap();
+
+        assert_eq!(
+            chip8.state(),
+            &ExecutionState::Watchpoint {
+                address: 0x200,
+                hit: WatchpointHit::MemoryWrite {
+                    address: 0x302,
+                    old: 0,
+                    new: 56
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn test_watch_sprite_read() {
+        let mut chip8 = build_chip_with_quirks(Quirks::SCHIP);
+        chip8.load(with_halt(vec![0xA3, 0x00, 0xD0, 0x05])).unwrap();
+        chip8.memory.set(0x304, 0xFF).unwrap();
+        chip8.add_watchpoint(Watchpoint::Memory {
+            range: 0x304..=0x304,
+            access: MemoryAccess::Read,
+        });
+
+        chip8.run_frame().unwrap();
+
+        assert_eq!(
+            chip8.state(),
+            &ExecutionState::Watchpoint {
+                address: 0x202,
+                hit: WatchpointHit::MemoryRead {
+                    address: 0x304,
+                    value: 0xFF
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn test_instruction_fetch_is_not_a_read() {
+        let mut chip8 = build_chip();
+        chip8.add_watchpoint(Watchpoint::Memory {
+            range: 0x200..=0x2FF,
+            access: MemoryAccess::Read,
+        });
+        chip8.load(with_halt(vec![0x60, 0x01, 0x00, 0xE0])).unwrap();
+
+        chip8.run().unwrap();
+
+        assert_eq!(chip8.state(), &ExecutionState::Halted);
+    }
+
+    #[test]
+    fn test_watch_register() {
+        let mut chip8 = build_chip();
+        chip8.add_watchpoint(Watchpoint::RegisterValue(VRegister::V1, 3));
+        chip8.add_watchpoint(Watchpoint::RegisterChange(VRegister::VF));
+        // V1 counts up from 1 by 1, adding into V2 which carries into VF when it overflows
+        chip8
+            .load(with_halt(vec![
+                0x61, 0x01, 0x62, 0xFE, 0x71, 0x01, 0x82, 0x14, 0x12, 0x04,
+            ]))
+            .unwrap();
+
+        chip8.run().unwrap();
+        assert_eq!(
+            chip8.state(),
+            &ExecutionState::Watchpoint {
+                address: 0x206,
+                hit: WatchpointHit::RegisterWrite {
+                    register: VRegister::VF,
+                    old: 0,
+                    new: 1
+                }
+            }
+        );
+
+        chip8.resume();
+        chip8.run().unwrap();
+        assert_eq!(
+            chip8.state(),
+            &ExecutionState::Watchpoint {
+                address: 0x204,
+                hit: WatchpointHit::RegisterWrite {
+                    register: VRegister::V1,
+                    old: 2,
+                    new: 3
+                }
+            }
+        );
+
+        chip8.clear_watchpoints();
+        chip8.resume();
+        chip8.run_instruction().unwrap();
+        assert_eq!(chip8.state(), &ExecutionState::Running);
+    }
+
+    #[test]
+    fn test_watchpoints_kept_on_load_state() {
+        let mut chip8 = build_chip();
+        chip8.load(with_halt(vec![0x60, 0x01])).unwrap();
+        let state = chip8.save_state();
+        chip8.add_watchpoint(Watchpoint::RegisterChange(VRegister::V0));
+
+        chip8.load_state(&state).unwrap();
+        chip8.run_instruction().unwrap();
+
+        assert!(matches!(
+            chip8.state(),
+            ExecutionState::Watchpoint { address: 0x200, .. }
+        ));
+        assert_eq!(chip8.watchpoints().len(), 1);
+    }
 }
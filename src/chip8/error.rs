@@ -0,0 +1,74 @@
+use std::{error::Error, fmt};
+
+/// Faults raised while executing a program
+#[derive(Debug, PartialEq, Clone)]
+pub enum Chip8Error {
+    /// The word at the given address is not a valid instruction
+    InvalidOpCode { address: u16, op_code: u16 },
+
+    /// The program counter points outside of memory
+    ProgramCounterOutOfRange { address: u16 },
+
+    /// A subroutine was called with all 16 stack levels in use
+    StackOverflow { address: u16 },
+
+    /// A subroutine return was executed with an empty stack
+    StackUnderflow { address: u16 },
+
+    /// The font only has sprites for the digits 0 through F
+    InvalidFontDigit { digit: u8 },
+
+    /// An instruction tried to access an address outside of memory
+    MemoryOutOfBounds { address: u16 },
+
+    /// The ROM does not fit in memory from the load address on
+    RomTooLarge { size: usize, capacity: usize },
+
+    /// The data is not a save state, or is truncated or corrupted
+    InvalidSaveState,
+
+    /// The save state was written by an incompatible version of the emulator
+    UnsupportedSaveStateVersion { version: u16 },
+
+    /// The save state was taken while running a different ROM
+    SaveStateRomMismatch,
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Chip8Error::InvalidOpCode { address, op_code } => {
+                write!(f, "Invalid opcode {:04x} at {:#05x}", op_code, address)
+            }
+            Chip8Error::ProgramCounterOutOfRange { address } => {
+                write!(f, "Program counter out of range at {:#05x}", address)
+            }
+            Chip8Error::StackOverflow { address } => {
+                write!(f, "Stack overflow calling subroutine at {:#05x}", address)
+            }
+            Chip8Error::StackUnderflow { address } => {
+                write!(f, "Stack underflow returning from {:#05x}", address)
+            }
+            Chip8Error::InvalidFontDigit { digit } => {
+                write!(f, "No font sprite for digit {:#04x}", digit)
+            }
+            Chip8Error::MemoryOutOfBounds { address } => {
+                write!(f, "Memory access out of bounds at {:#06x}", address)
+            }
+            Chip8Error::RomTooLarge { size, capacity } => write!(
+                f,
+                "ROM is {} bytes long, but only {} bytes fit in memory",
+                size, capacity
+            ),
+            Chip8Error::InvalidSaveState => write!(f, "Invalid save state"),
+            Chip8Error::UnsupportedSaveStateVersion { version } => {
+                write!(f, "Unsupported save state version {}", version)
+            }
+            Chip8Error::SaveStateRomMismatch => {
+                write!(f, "Save state was taken with a different ROM")
+            }
+        }
+    }
+}
+
+impl Error for Chip8Error {}
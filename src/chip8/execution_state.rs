@@ -0,0 +1,31 @@
+use super::{Chip8Error, WatchpointHit};
+
+/// What the interpreter is currently doing
+#[derive(Debug, PartialEq, Clone)]
+pub enum ExecutionState {
+    /// Executing instructions normally
+    Running,
+
+    /// Blocked on `Fx0A` until a key is pressed and released
+    WaitingForKey,
+
+    /// The program reached a point it can never leave, such as a jump to itself
+    Halted,
+
+    /// Execution stopped because the program did something invalid
+    Faulted(Chip8Error),
+
+    /// Execution stopped after the instruction at `address` triggered a watchpoint, until
+    /// `Chip8::resume` is called
+    Watchpoint { address: u16, hit: WatchpointHit },
+}
+
+impl ExecutionState {
+    /// Whether instructions are still being executed, which includes waiting for a key
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self,
+            ExecutionState::Running | ExecutionState::WaitingForKey
+        )
+    }
+}
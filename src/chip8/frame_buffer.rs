@@ -1,57 +1,256 @@
-use std::ops::Deref;
+use super::{Chip8Error, Snapshot, StateReader, StateWriter};
 
-const DISPLAY_WIDTH: usize = 64;
-const DISPLAY_HEIGHT: usize = 32;
+const LOW_RESOLUTION_WIDTH: usize = 64;
+const LOW_RESOLUTION_HEIGHT: usize = 32;
+const HIGH_RESOLUTION_WIDTH: usize = 128;
+const HIGH_RESOLUTION_HEIGHT: usize = 64;
 const SPRITE_WIDTH: usize = 8;
+const LARGE_SPRITE_WIDTH: usize = 16;
 
-pub type FrameBufferMemory = [u64; DISPLAY_HEIGHT];
+/// Number of XO-CHIP bitplanes, which combined give each pixel one of 4 colours
+pub const PLANE_COUNT: usize = 2;
 
-#[derive(Default)]
-pub struct FrameBuffer(FrameBufferMemory);
+/// One row per line of the screen, with the leftmost pixel in the most significant bit in use.
+/// In low resolution mode only the first 32 rows and the lower 64 bits of each row are used.
+pub type FrameBufferMemory = [u128; HIGH_RESOLUTION_HEIGHT];
+
+#[derive(Clone, Copy)]
+pub struct FrameBuffer {
+    planes: [FrameBufferMemory; PLANE_COUNT],
+    selected_planes: u8,
+    high_resolution: bool,
+}
 
 impl FrameBuffer {
-    pub fn draw(&mut self, (x, y): (u8, u8), sprite: &[u8]) -> bool {
+    /// XORs a sprite onto the selected planes, returning whether any pixel was turned off
+    ///
+    /// The sprite holds one layer per selected plane, one after the other, starting with the
+    /// lowest plane. The starting position always wraps around the screen. Parts of the sprite
+    /// that go past the edges either wrap around as well or get dropped when `clip` is set.
+    pub fn draw(&mut self, position: (u8, u8), sprite: &[u8], clip: bool) -> bool {
+        self.draw_layers(position, sprite, SPRITE_WIDTH, clip)
+    }
+
+    /// Same as `draw`, but for SUPER-CHIP 16x16 sprites stored as 16 pairs of bytes per layer
+    pub fn draw_large(&mut self, position: (u8, u8), sprite: &[u8], clip: bool) -> bool {
+        self.draw_layers(position, sprite, LARGE_SPRITE_WIDTH, clip)
+    }
+
+    fn draw_layers(
+        &mut self,
+        position: (u8, u8),
+        sprite: &[u8],
+        sprite_width: usize,
+        clip: bool,
+    ) -> bool {
+        let planes: Vec<usize> = self.selected_planes().collect();
+
+        if planes.is_empty() || sprite.is_empty() {
+            return false;
+        }
+
+        let layer_length = sprite.len() / planes.len();
+        let bytes_per_row = sprite_width / 8;
+        let mut turned_bit_off = false;
+
+        for (plane, layer) in planes.into_iter().zip(sprite.chunks(layer_length)) {
+            let rows = layer
+                .chunks(bytes_per_row)
+                .map(|bytes| bytes.iter().fold(0, |row, byte| row << 8 | *byte as u128));
+
+            if self.draw_rows(plane, position, rows, sprite_width, clip) {
+                turned_bit_off = true;
+            }
+        }
+
+        turned_bit_off
+    }
+
+    fn draw_rows(
+        &mut self,
+        plane: usize,
+        (x, y): (u8, u8),
+        sprite: impl Iterator<Item = u128>,
+        sprite_width: usize,
+        clip: bool,
+    ) -> bool {
+        let width = self.get_screen_width();
+        let height = self.get_screen_height();
+        let rows = &mut self.planes[plane];
         let mut turned_bit_off = false;
 
-        let mut y = y as usize;
+        let x = x as usize % width;
+        let mut y = y as usize % height;
 
-        for byte in sprite {
-            let byte = ((*byte as u64) << (DISPLAY_WIDTH - SPRITE_WIDTH)).rotate_right(x as u32);
-            let previous = self.0[y];
-            let updated = previous ^ byte;
+        for row in sprite {
+            let row = row << (width - sprite_width);
+            let row = if clip {
+                row >> x
+            } else {
+                rotate_right(row, x, width)
+            };
+            let previous = rows[y];
+            let updated = previous ^ row;
 
             if previous & !updated != 0 {
                 turned_bit_off = true;
             }
 
-            self.0[y] = updated;
+            rows[y] = updated;
 
             y += 1;
-            y %= DISPLAY_HEIGHT;
+
+            if y == height {
+                if clip {
+                    break;
+                }
+
+                y = 0;
+            }
         }
 
         turned_bit_off
     }
 
+    /// Clears the selected planes
     pub fn clear(&mut self) {
-        *self = Default::default();
+        for plane in self.selected_planes() {
+            self.planes[plane] = [0; HIGH_RESOLUTION_HEIGHT];
+        }
+    }
+
+    /// Moves the contents of the selected planes down by the given number of rows, blanking the
+    /// top ones
+    pub fn scroll_down(&mut self, rows: usize) {
+        let height = self.get_screen_height();
+        let rows = rows.min(height);
+
+        for plane in self.selected_planes() {
+            let plane = &mut self.planes[plane];
+            plane.copy_within(0..height - rows, rows);
+            plane[..rows].fill(0);
+        }
+    }
+
+    /// Moves the contents of the selected planes right by the given number of pixels
+    pub fn scroll_right(&mut self, pixels: usize) {
+        let height = self.get_screen_height();
+
+        for plane in self.selected_planes() {
+            for row in &mut self.planes[plane][..height] {
+                *row >>= pixels;
+            }
+        }
+    }
+
+    /// Moves the contents of the selected planes left by the given number of pixels
+    pub fn scroll_left(&mut self, pixels: usize) {
+        let height = self.get_screen_height();
+        let mask = row_mask(self.get_screen_width());
+
+        for plane in self.selected_planes() {
+            for row in &mut self.planes[plane][..height] {
+                *row = (*row << pixels) & mask;
+            }
+        }
+    }
+
+    /// Picks the planes affected by drawing, clearing and scrolling, one bit per plane
+    pub fn select_planes(&mut self, planes: u8) {
+        self.selected_planes = planes & ((1 << PLANE_COUNT) - 1);
+    }
+
+    pub fn selected_plane_count(&self) -> usize {
+        self.selected_planes().count()
+    }
+
+    fn selected_planes(&self) -> impl Iterator<Item = usize> {
+        let selected_planes = self.selected_planes;
+        (0..PLANE_COUNT).filter(move |plane| selected_planes & (1 << plane) != 0)
+    }
+
+    /// Switches between the 64x32 and the SUPER-CHIP 128x64 modes, clearing every plane
+    pub fn set_high_resolution(&mut self, high_resolution: bool) {
+        self.high_resolution = high_resolution;
+        self.planes = [[0; HIGH_RESOLUTION_HEIGHT]; PLANE_COUNT];
+    }
+
+    /// Value of the pixel at the given position, combining one bit from each plane. Renderers can
+    /// use it as an index into a palette of `1 << PLANE_COUNT` colours.
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        let shift = self.get_screen_width() - x - 1;
+
+        self.planes
+            .iter()
+            .enumerate()
+            .map(|(plane, rows)| (((rows[y] >> shift) & 1) as u8) << plane)
+            .sum()
     }
 
     pub fn get_screen_width(&self) -> usize {
-        DISPLAY_WIDTH
+        if self.high_resolution {
+            HIGH_RESOLUTION_WIDTH
+        } else {
+            LOW_RESOLUTION_WIDTH
+        }
     }
 
     pub fn get_screen_height(&self) -> usize {
-        DISPLAY_HEIGHT
+        if self.high_resolution {
+            HIGH_RESOLUTION_HEIGHT
+        } else {
+            LOW_RESOLUTION_HEIGHT
+        }
     }
 }
 
-impl Deref for FrameBuffer {
-    type Target = [u64; 32];
+impl Snapshot for FrameBuffer {
+    fn save(&self, writer: &mut StateWriter) {
+        writer.bool(self.high_resolution);
+        writer.u8(self.selected_planes);
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+        for row in self.planes.iter().flatten() {
+            writer.u128(*row);
+        }
     }
+
+    fn restore(reader: &mut StateReader) -> Result<Self, Chip8Error> {
+        let mut buffer = FrameBuffer {
+            high_resolution: reader.bool()?,
+            ..FrameBuffer::default()
+        };
+        buffer.select_planes(reader.u8()?);
+
+        for row in buffer.planes.iter_mut().flatten() {
+            *row = reader.u128()?;
+        }
+
+        Ok(buffer)
+    }
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        FrameBuffer {
+            planes: [[0; HIGH_RESOLUTION_HEIGHT]; PLANE_COUNT],
+            selected_planes: 0b01,
+            high_resolution: false,
+        }
+    }
+}
+
+/// Rotates the lower `width` bits of `value`
+fn rotate_right(value: u128, amount: usize, width: usize) -> u128 {
+    if amount == 0 {
+        return value;
+    }
+
+    ((value >> amount) | (value << (width - amount))) & row_mask(width)
+}
+
+/// Bits of a row that hold pixels for a screen of the given width
+fn row_mask(width: usize) -> u128 {
+    u128::MAX >> (u128::BITS as usize - width)
 }
 
 #[cfg(test)]
@@ -61,9 +260,9 @@ mod tests {
     #[test]
     fn draw_single_byte_sprite() {
         let mut buffer = FrameBuffer::default();
-        buffer.draw((1, 2), &[0b10011001]);
+        buffer.draw((1, 2), &[0b10011001], false);
         assert_eq!(
-            buffer.0[2],
+            buffer.planes[0][2],
             0b0100110010000000000000000000000000000000000000000000000000000000
         );
     }
@@ -71,9 +270,9 @@ mod tests {
     #[test]
     fn draw_multiple_byte_sprite() {
         let mut buffer = FrameBuffer::default();
-        buffer.draw((1, 2), &[0b10011001, 0b01100110]);
+        buffer.draw((1, 2), &[0b10011001, 0b01100110], false);
         assert_eq!(
-            &buffer.0[2..=3],
+            &buffer.planes[0][2..=3],
             &[
                 0b0100110010000000000000000000000000000000000000000000000000000000,
                 0b0011001100000000000000000000000000000000000000000000000000000000,
@@ -84,9 +283,9 @@ mod tests {
     #[test]
     fn draw_byte_wrapping_around_horizontally() {
         let mut buffer = FrameBuffer::default();
-        buffer.draw((60, 2), &[0b10011001]);
+        buffer.draw((60, 2), &[0b10011001], false);
         assert_eq!(
-            buffer.0[2],
+            buffer.planes[0][2],
             0b1001000000000000000000000000000000000000000000000000000000001001,
         );
     }
@@ -94,13 +293,13 @@ mod tests {
     #[test]
     fn draw_sprite_wrapping_around_vertically() {
         let mut buffer = FrameBuffer::default();
-        buffer.draw((0, 31), &[0b10011001, 0b01100110]);
+        buffer.draw((0, 31), &[0b10011001, 0b01100110], false);
         assert_eq!(
-            buffer.0[31],
+            buffer.planes[0][31],
             0b1001100100000000000000000000000000000000000000000000000000000000,
         );
         assert_eq!(
-            buffer.0[0],
+            buffer.planes[0][0],
             0b0110011000000000000000000000000000000000000000000000000000000000,
         );
     }
@@ -108,13 +307,162 @@ mod tests {
     #[test]
     fn returns_false_if_no_bit_was_flipped_off() {
         let mut buffer = FrameBuffer::default();
-        assert!(!buffer.draw((0, 0), &[0b11111111]));
+        assert!(!buffer.draw((0, 0), &[0b11111111], false));
     }
 
     #[test]
     fn returns_true_if_some_bit_was_flipped_off() {
         let mut buffer = FrameBuffer::default();
-        buffer.draw((0, 0), &[0b11111111]);
-        assert!(buffer.draw((0, 0), &[0b11111110]));
+        buffer.draw((0, 0), &[0b11111111], false);
+        assert!(buffer.draw((0, 0), &[0b11111110], false));
+    }
+
+    #[test]
+    fn draw_byte_clipped_horizontally() {
+        let mut buffer = FrameBuffer::default();
+        buffer.draw((60, 2), &[0b10011001], true);
+        assert_eq!(
+            buffer.planes[0][2],
+            0b0000000000000000000000000000000000000000000000000000000000001001,
+        );
+    }
+
+    #[test]
+    fn draw_sprite_clipped_vertically() {
+        let mut buffer = FrameBuffer::default();
+        buffer.draw((0, 31), &[0b10011001, 0b01100110], true);
+        assert_eq!(
+            buffer.planes[0][31],
+            0b1001100100000000000000000000000000000000000000000000000000000000,
+        );
+        assert_eq!(buffer.planes[0][0], 0);
+    }
+
+    #[test]
+    fn draw_wraps_starting_position() {
+        let mut buffer = FrameBuffer::default();
+        buffer.draw((65, 34), &[0b10011001], true);
+        assert_eq!(
+            buffer.planes[0][2],
+            0b0100110010000000000000000000000000000000000000000000000000000000
+        );
+    }
+
+    #[test]
+    fn draw_in_high_resolution() {
+        let mut buffer = FrameBuffer::default();
+        buffer.set_high_resolution(true);
+        buffer.draw((120, 40), &[0b10011001], false);
+        assert_eq!(buffer.planes[0][40], 0b10011001);
+    }
+
+    #[test]
+    fn draw_wrapping_in_high_resolution() {
+        let mut buffer = FrameBuffer::default();
+        buffer.set_high_resolution(true);
+        buffer.draw((124, 63), &[0b10011001, 0b11111111], false);
+        assert_eq!(buffer.planes[0][63], 0b1001 << 124 | 0b1001);
+        assert_eq!(buffer.planes[0][0], 0b1111 << 124 | 0b1111);
+    }
+
+    #[test]
+    fn draw_large_sprite() {
+        let mut buffer = FrameBuffer::default();
+        buffer.set_high_resolution(true);
+        let mut sprite = [0; 32];
+        sprite[0] = 0b10000000;
+        sprite[1] = 0b00000001;
+        sprite[31] = 0b11111111;
+        buffer.draw_large((0, 0), &sprite, false);
+        assert_eq!(buffer.planes[0][0], 0b1000000000000001 << 112);
+        assert_eq!(buffer.planes[0][15], 0b0000000011111111 << 112);
+    }
+
+    #[test]
+    fn switching_resolution_clears_the_screen() {
+        let mut buffer = FrameBuffer::default();
+        buffer.draw((0, 0), &[0b11111111], false);
+        buffer.set_high_resolution(true);
+        assert_eq!(buffer.get_screen_width(), 128);
+        assert_eq!(buffer.get_screen_height(), 64);
+        assert!(buffer.planes[0].iter().all(|row| *row == 0));
+    }
+
+    #[test]
+    fn scroll_down() {
+        let mut buffer = FrameBuffer::default();
+        buffer.draw((0, 0), &[0b11111111], false);
+        buffer.draw((0, 30), &[0b11111111], false);
+        buffer.scroll_down(2);
+        assert_eq!(buffer.planes[0][0], 0);
+        assert_eq!(buffer.planes[0][2], 0xFF << 56);
+        // Rows pushed past the bottom of the screen are lost
+        assert_eq!(buffer.planes[0][32], 0);
+    }
+
+    #[test]
+    fn scroll_right() {
+        let mut buffer = FrameBuffer::default();
+        buffer.draw((60, 0), &[0b11111111], false);
+        buffer.scroll_right(4);
+        assert_eq!(buffer.planes[0][0], 0b1111 << 56);
+    }
+
+    #[test]
+    fn scroll_left() {
+        let mut buffer = FrameBuffer::default();
+        buffer.draw((0, 0), &[0b11111111], false);
+        buffer.scroll_left(4);
+        assert_eq!(buffer.planes[0][0], 0b1111 << 60);
+    }
+
+    #[test]
+    fn scroll_left_in_high_resolution() {
+        let mut buffer = FrameBuffer::default();
+        buffer.set_high_resolution(true);
+        buffer.draw((0, 0), &[0b11111111], false);
+        buffer.scroll_left(4);
+        assert_eq!(buffer.planes[0][0], 0b1111 << 124);
+    }
+
+    #[test]
+    fn draw_one_layer_per_selected_plane() {
+        let mut buffer = FrameBuffer::default();
+        buffer.select_planes(0b11);
+        buffer.draw((0, 0), &[0b11110000, 0b10101010], false);
+        assert_eq!(buffer.planes[0][0], 0b11110000 << 56);
+        assert_eq!(buffer.planes[1][0], 0b10101010 << 56);
+        assert_eq!(buffer.pixel(0, 0), 3);
+        assert_eq!(buffer.pixel(1, 0), 1);
+        assert_eq!(buffer.pixel(4, 0), 2);
+        assert_eq!(buffer.pixel(5, 0), 0);
+    }
+
+    #[test]
+    fn draw_only_on_second_plane() {
+        let mut buffer = FrameBuffer::default();
+        buffer.select_planes(0b10);
+        buffer.draw((0, 0), &[0b11111111], false);
+        assert_eq!(buffer.planes[0][0], 0);
+        assert_eq!(buffer.planes[1][0], 0xFF << 56);
+    }
+
+    #[test]
+    fn draw_with_no_plane_selected() {
+        let mut buffer = FrameBuffer::default();
+        buffer.select_planes(0);
+        assert!(!buffer.draw((0, 0), &[0b11111111], false));
+        assert_eq!(buffer.pixel(0, 0), 0);
+    }
+
+    #[test]
+    fn clear_only_selected_planes() {
+        let mut buffer = FrameBuffer::default();
+        buffer.select_planes(0b11);
+        buffer.draw((0, 0), &[0b11111111, 0b11111111], false);
+        buffer.select_planes(0b01);
+        buffer.clear();
+        assert_eq!(buffer.planes[0][0], 0);
+        assert_eq!(buffer.planes[1][0], 0xFF << 56);
     }
 }
@@ -1,5 +1,6 @@
-use super::InputInstruction;
+use std::convert::TryFrom;
 
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum InputKey {
     Key0,
     Key1,
@@ -19,9 +20,9 @@ pub enum InputKey {
     KeyF,
 }
 
-impl Into<u8> for InputKey {
-    fn into(self) -> u8 {
-        match self {
+impl From<InputKey> for u8 {
+    fn from(key: InputKey) -> Self {
+        match key {
             InputKey::Key0 => 0x0,
             InputKey::Key1 => 0x1,
             InputKey::Key2 => 0x2,
@@ -42,8 +43,28 @@ impl Into<u8> for InputKey {
     }
 }
 
-impl Into<InputInstruction> for InputKey {
-    fn into(self) -> InputInstruction {
-        InputInstruction::new(self)
+impl TryFrom<u8> for InputKey {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x0 => Ok(InputKey::Key0),
+            0x1 => Ok(InputKey::Key1),
+            0x2 => Ok(InputKey::Key2),
+            0x3 => Ok(InputKey::Key3),
+            0x4 => Ok(InputKey::Key4),
+            0x5 => Ok(InputKey::Key5),
+            0x6 => Ok(InputKey::Key6),
+            0x7 => Ok(InputKey::Key7),
+            0x8 => Ok(InputKey::Key8),
+            0x9 => Ok(InputKey::Key9),
+            0xA => Ok(InputKey::KeyA),
+            0xB => Ok(InputKey::KeyB),
+            0xC => Ok(InputKey::KeyC),
+            0xD => Ok(InputKey::KeyD),
+            0xE => Ok(InputKey::KeyE),
+            0xF => Ok(InputKey::KeyF),
+            _ => Err(()),
+        }
     }
 }
@@ -1,27 +1,115 @@
-use super::{FrameBufferMemory, InputKey};
+use std::sync::{
+    mpsc::{Receiver, Sender},
+    Arc, Mutex,
+};
 
-pub struct DrawInstruction(FrameBufferMemory);
+use super::{FrameBuffer, InputKey, Sound};
+
+/// Receives the screen whenever the running program draws on it
+pub trait Display {
+    fn draw(&mut self, buffer: &FrameBuffer);
+}
+
+/// Source of the key presses and releases on the host
+pub trait Keypad {
+    /// Next key event since the last poll, if any. Must not block.
+    fn poll(&mut self) -> Option<InputInstruction>;
+}
+
+/// Receives the sound state whenever the sound timer, pattern or pitch changes
+pub trait Audio {
+    fn update(&mut self, sound: &Sound);
+}
+
+/// A snapshot of every plane of the screen, sent whenever something is drawn
+pub struct DrawInstruction(FrameBuffer);
 
 impl DrawInstruction {
-    pub fn new(buffer: FrameBufferMemory) -> DrawInstruction {
+    pub fn new(buffer: FrameBuffer) -> DrawInstruction {
         DrawInstruction(buffer)
     }
 
-    pub fn buffer(&self) -> &FrameBufferMemory {
+    pub fn buffer(&self) -> &FrameBuffer {
         &self.0
     }
 }
 
-pub struct InputInstruction(InputKey);
+/// A key of the keypad going down or up on the host
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum InputInstruction {
+    Press(InputKey),
+    Release(InputKey),
+}
+
+/// Sends frames to a renderer running in another thread. Frames are dropped once the receiving
+/// side is gone.
+impl Display for Sender<DrawInstruction> {
+    fn draw(&mut self, buffer: &FrameBuffer) {
+        self.send(DrawInstruction::new(*buffer)).ok();
+    }
+}
+
+/// Takes key events sent from another thread
+impl Keypad for Receiver<InputInstruction> {
+    fn poll(&mut self) -> Option<InputInstruction> {
+        self.try_recv().ok()
+    }
+}
 
-impl InputInstruction {
-    pub fn new(input_key: InputKey) -> InputInstruction {
-        InputInstruction(input_key)
+/// Shares the sound state with an audio callback running in another thread, which generates the
+/// samples from it
+impl Audio for Arc<Mutex<Sound>> {
+    fn update(&mut self, sound: &Sound) {
+        self.lock().unwrap().sync(sound);
     }
 }
 
-impl Into<InputKey> for InputInstruction {
-    fn into(self) -> InputKey {
-        self.0
+/// Backend that ignores every output and never reports a key, for running without a frontend
+pub struct Headless;
+
+impl Display for Headless {
+    fn draw(&mut self, _buffer: &FrameBuffer) {}
+}
+
+impl Keypad for Headless {
+    fn poll(&mut self) -> Option<InputInstruction> {
+        None
+    }
+}
+
+impl Audio for Headless {
+    fn update(&mut self, _sound: &Sound) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn draw_after_receiver_dropped() {
+        let (mut tx, rx) = channel::<DrawInstruction>();
+        drop(rx);
+        tx.draw(&FrameBuffer::default());
+    }
+
+    #[test]
+    fn poll_channel_keypad() {
+        let (tx, mut rx) = channel();
+        assert_eq!(rx.poll(), None);
+        tx.send(InputInstruction::Press(InputKey::Key7)).unwrap();
+        tx.send(InputInstruction::Release(InputKey::Key7)).unwrap();
+        assert_eq!(rx.poll(), Some(InputInstruction::Press(InputKey::Key7)));
+        assert_eq!(rx.poll(), Some(InputInstruction::Release(InputKey::Key7)));
+        assert_eq!(rx.poll(), None);
+    }
+
+    #[test]
+    fn shared_sound_follows_updates() {
+        let mut shared = Arc::new(Mutex::new(Sound::default()));
+        let mut sound = Sound::default();
+        sound.set_active(true);
+        shared.update(&sound);
+        assert!(shared.lock().unwrap().is_active());
     }
 }
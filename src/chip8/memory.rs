@@ -1,149 +1,375 @@
-use std::ops::Range;
+use std::cell::Cell;
 
-pub struct Memory([u8; 0x1000]);
+use super::{Chip8Error, Snapshot, StateReader, StateWriter, Watchpoint, WatchpointHit};
+
+/// Memory available to CHIP-8 and SUPER-CHIP programs
+pub const MEMORY_SIZE: usize = 0x1000;
+
+/// Memory available to XO-CHIP programs, the whole 16 bit address space
+pub const EXTENDED_MEMORY_SIZE: usize = 0x10000;
+
+pub struct Memory {
+    bytes: Vec<u8>,
+    watchpoints: Vec<Watchpoint>,
+    /// First watched access since the last `take_watchpoint_hit`, reads only borrow memory
+    hit: Cell<Option<WatchpointHit>>,
+}
 
 const FONT_BASE: u16 = 0;
+const LARGE_FONT_BASE: u16 = 0x50;
+
+/// Hexadecimal digits 0 through F, 5 bytes each
+#[rustfmt::skip]
+const FONT: [u8; 80] = [
+    // 0
+    0b01100000, 0b10010000, 0b10010000, 0b10010000, 0b01100000,
+    // 1
+    0b01100000, 0b00100000, 0b00100000, 0b00100000, 0b01110000,
+    // 2
+    0b11100000, 0b00010000, 0b00110000, 0b01100000, 0b11110000,
+    // 3
+    0b11100000, 0b00010000, 0b01100000, 0b00010000, 0b11100000,
+    // 4
+    0b10010000, 0b10010000, 0b11110000, 0b00010000, 0b00010000,
+    // 5
+    0b11110000, 0b10000000, 0b11110000, 0b00010000, 0b11110000,
+    // 6
+    0b11110000, 0b10000000, 0b11110000, 0b10010000, 0b11110000,
+    // 7
+    0b11110000, 0b00010000, 0b00100000, 0b01000000, 0b01000000,
+    // 8
+    0b11110000, 0b10010000, 0b11110000, 0b10010000, 0b11110000,
+    // 9
+    0b11110000, 0b10010000, 0b11110000, 0b00010000, 0b11110000,
+    // A
+    0b11110000, 0b10010000, 0b11110000, 0b10010000, 0b10010000,
+    // B
+    0b11100000, 0b10010000, 0b11100000, 0b10010000, 0b11100000,
+    // C
+    0b11110000, 0b10000000, 0b10000000, 0b10000000, 0b11110000,
+    // D
+    0b11100000, 0b10010000, 0b10010000, 0b10010000, 0b11100000,
+    // E
+    0b11110000, 0b10000000, 0b11110000, 0b10000000, 0b11110000,
+    // F
+    0b11110000, 0b10000000, 0b11100000, 0b10000000, 0b10000000,
+];
+
+/// SUPER-CHIP large hexadecimal digits 0 through F, 10 bytes each
+#[rustfmt::skip]
+const LARGE_FONT: [u8; 160] = [
+    // 0
+    0b11111111, 0b11111111, 0b11000011, 0b11000011, 0b11000011,
+    0b11000011, 0b11000011, 0b11000011, 0b11111111, 0b11111111,
+    // 1
+    0b00011000, 0b01111000, 0b01111000, 0b00011000, 0b00011000,
+    0b00011000, 0b00011000, 0b00011000, 0b11111111, 0b11111111,
+    // 2
+    0b11111111, 0b11111111, 0b00000011, 0b00000011, 0b11111111,
+    0b11111111, 0b11000000, 0b11000000, 0b11111111, 0b11111111,
+    // 3
+    0b11111111, 0b11111111, 0b00000011, 0b00000011, 0b11111111,
+    0b11111111, 0b00000011, 0b00000011, 0b11111111, 0b11111111,
+    // 4
+    0b11000011, 0b11000011, 0b11000011, 0b11000011, 0b11111111,
+    0b11111111, 0b00000011, 0b00000011, 0b00000011, 0b00000011,
+    // 5
+    0b11111111, 0b11111111, 0b11000000, 0b11000000, 0b11111111,
+    0b11111111, 0b00000011, 0b00000011, 0b11111111, 0b11111111,
+    // 6
+    0b11111111, 0b11111111, 0b11000000, 0b11000000, 0b11111111,
+    0b11111111, 0b11000011, 0b11000011, 0b11111111, 0b11111111,
+    // 7
+    0b11111111, 0b11111111, 0b00000011, 0b00000011, 0b00000110,
+    0b00001100, 0b00011000, 0b00011000, 0b00011000, 0b00011000,
+    // 8
+    0b11111111, 0b11111111, 0b11000011, 0b11000011, 0b11111111,
+    0b11111111, 0b11000011, 0b11000011, 0b11111111, 0b11111111,
+    // 9
+    0b11111111, 0b11111111, 0b11000011, 0b11000011, 0b11111111,
+    0b11111111, 0b00000011, 0b00000011, 0b11111111, 0b11111111,
+    // A
+    0b01111110, 0b11111111, 0b11000011, 0b11000011, 0b11000011,
+    0b11111111, 0b11111111, 0b11000011, 0b11000011, 0b11000011,
+    // B
+    0b11111100, 0b11111100, 0b11000011, 0b11000011, 0b11111100,
+    0b11111100, 0b11000011, 0b11000011, 0b11111100, 0b11111100,
+    // C
+    0b00111100, 0b11111111, 0b11000011, 0b11000000, 0b11000000,
+    0b11000000, 0b11000000, 0b11000011, 0b11111111, 0b00111100,
+    // D
+    0b11111100, 0b11111110, 0b11000011, 0b11000011, 0b11000011,
+    0b11000011, 0b11000011, 0b11000011, 0b11111110, 0b11111100,
+    // E
+    0b11111111, 0b11111111, 0b11000000, 0b11000000, 0b11111111,
+    0b11111111, 0b11000000, 0b11000000, 0b11111111, 0b11111111,
+    // F
+    0b11111111, 0b11111111, 0b11000000, 0b11000000, 0b11111111,
+    0b11111111, 0b11000000, 0b11000000, 0b11000000, 0b11000000,
+];
 
 impl Memory {
-    pub fn get(&self, index: u16) -> u8 {
-        self.0[index as usize]
+    pub fn get(&self, index: u16) -> Result<u8, Chip8Error> {
+        let value = self.peek(index)?;
+        self.watch_reads(index, &[value]);
+        Ok(value)
+    }
+
+    /// Reads a byte without triggering watchpoints, for fetching instructions and for debuggers
+    pub fn peek(&self, index: u16) -> Result<u8, Chip8Error> {
+        self.bytes
+            .get(index as usize)
+            .copied()
+            .ok_or(Chip8Error::MemoryOutOfBounds { address: index })
+    }
+
+    pub fn set(&mut self, index: u16, value: u8) -> Result<(), Chip8Error> {
+        let byte = self
+            .bytes
+            .get_mut(index as usize)
+            .ok_or(Chip8Error::MemoryOutOfBounds { address: index })?;
+        let old = *byte;
+        *byte = value;
+
+        if self
+            .watchpoints
+            .iter()
+            .any(|watchpoint| watchpoint.is_memory_write(index))
+        {
+            self.record_hit(WatchpointHit::MemoryWrite {
+                address: index,
+                old,
+                new: value,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Reads `length` bytes from `start`, failing with the first address past the end of memory
+    /// when they don't all fit. That address wraps around past the 64 KiB address space.
+    pub fn range(&self, start: u16, length: usize) -> Result<&[u8], Chip8Error> {
+        let range = start as usize..start as usize + length;
+        let bytes = self.bytes.get(range).ok_or(Chip8Error::MemoryOutOfBounds {
+            address: self.bytes.len().max(start as usize) as u16,
+        })?;
+
+        self.watch_reads(start, bytes);
+        Ok(bytes)
+    }
+
+    /// Number of bytes of memory, depending on the platform
+    pub fn size(&self) -> usize {
+        self.bytes.len()
     }
 
-    pub fn set(&mut self, index: u16, value: u8) {
-        self.0[index as usize] = value;
+    /// Replaces the watchpoints checked on every access, keeping only the memory ones
+    pub fn set_watchpoints(&mut self, watchpoints: &[Watchpoint]) {
+        self.watchpoints = watchpoints
+            .iter()
+            .filter(|watchpoint| matches!(watchpoint, Watchpoint::Memory { .. }))
+            .cloned()
+            .collect();
     }
 
-    pub fn range(&self, range: Range<u16>) -> &[u8] {
-        let range = range.start as usize..range.end as usize;
-        &self.0[range]
+    /// Returns the first watched access since the last call
+    pub fn take_watchpoint_hit(&mut self) -> Option<WatchpointHit> {
+        self.hit.take()
     }
 
-    pub fn index_of_char(&self, character: u8) -> u16 {
+    fn watch_reads(&self, start: u16, bytes: &[u8]) {
+        if self.watchpoints.is_empty() {
+            return;
+        }
+
+        let watched = bytes
+            .iter()
+            .enumerate()
+            .map(|(offset, value)| (start.wrapping_add(offset as u16), *value))
+            .find(|(address, _)| {
+                self.watchpoints
+                    .iter()
+                    .any(|watchpoint| watchpoint.is_memory_read(*address))
+            });
+
+        if let Some((address, value)) = watched {
+            self.record_hit(WatchpointHit::MemoryRead { address, value });
+        }
+    }
+
+    fn record_hit(&self, hit: WatchpointHit) {
+        if self.hit.get().is_none() {
+            self.hit.set(Some(hit));
+        }
+    }
+
+    pub fn index_of_char(&self, character: u8) -> Result<u16, Chip8Error> {
+        if character < 16 {
+            Ok(FONT_BASE + character as u16 * 5)
+        } else {
+            Err(Chip8Error::InvalidFontDigit { digit: character })
+        }
+    }
+
+    pub fn index_of_large_char(&self, character: u8) -> Result<u16, Chip8Error> {
         if character < 16 {
-            FONT_BASE + character as u16 * 5
+            Ok(LARGE_FONT_BASE + character as u16 * 10)
         } else {
-            unreachable!()
+            Err(Chip8Error::InvalidFontDigit { digit: character })
         }
     }
 }
 
-impl Default for Memory {
-    fn default() -> Self {
-        let mut memory = Memory([0; 0x1000]);
+impl Memory {
+    pub fn new(size: usize) -> Self {
+        let mut memory = Memory {
+            bytes: vec![0; size],
+            watchpoints: Vec::new(),
+            hit: Cell::new(None),
+        };
 
         // Set the font in memory
-        // 0
-        memory.set(FONT_BASE + 0, 0b01100000);
-        memory.set(FONT_BASE + 1, 0b10010000);
-        memory.set(FONT_BASE + 2, 0b10010000);
-        memory.set(FONT_BASE + 3, 0b10010000);
-        memory.set(FONT_BASE + 4, 0b01100000);
-
-        // 1
-        memory.set(FONT_BASE + 5, 0b01100000);
-        memory.set(FONT_BASE + 6, 0b00100000);
-        memory.set(FONT_BASE + 7, 0b00100000);
-        memory.set(FONT_BASE + 8, 0b00100000);
-        memory.set(FONT_BASE + 9, 0b01110000);
-
-        // 2
-        memory.set(FONT_BASE + 10, 0b11100000);
-        memory.set(FONT_BASE + 11, 0b00010000);
-        memory.set(FONT_BASE + 12, 0b00110000);
-        memory.set(FONT_BASE + 13, 0b01100000);
-        memory.set(FONT_BASE + 14, 0b11110000);
-
-        // 3
-        memory.set(FONT_BASE + 15, 0b11100000);
-        memory.set(FONT_BASE + 16, 0b00010000);
-        memory.set(FONT_BASE + 17, 0b01100000);
-        memory.set(FONT_BASE + 18, 0b00010000);
-        memory.set(FONT_BASE + 19, 0b11100000);
-
-        // 4
-        memory.set(FONT_BASE + 20, 0b10010000);
-        memory.set(FONT_BASE + 21, 0b10010000);
-        memory.set(FONT_BASE + 22, 0b11110000);
-        memory.set(FONT_BASE + 23, 0b00010000);
-        memory.set(FONT_BASE + 24, 0b00010000);
-
-        // 5
-        memory.set(FONT_BASE + 25, 0b11110000);
-        memory.set(FONT_BASE + 26, 0b10000000);
-        memory.set(FONT_BASE + 27, 0b11110000);
-        memory.set(FONT_BASE + 28, 0b00010000);
-        memory.set(FONT_BASE + 29, 0b11110000);
-
-        // 6
-        memory.set(FONT_BASE + 30, 0b11110000);
-        memory.set(FONT_BASE + 31, 0b10000000);
-        memory.set(FONT_BASE + 32, 0b11110000);
-        memory.set(FONT_BASE + 33, 0b10010000);
-        memory.set(FONT_BASE + 34, 0b11110000);
-
-        // 7
-        memory.set(FONT_BASE + 35, 0b11110000);
-        memory.set(FONT_BASE + 36, 0b00010000);
-        memory.set(FONT_BASE + 37, 0b00100000);
-        memory.set(FONT_BASE + 38, 0b01000000);
-        memory.set(FONT_BASE + 39, 0b01000000);
-
-        // 8
-        memory.set(FONT_BASE + 40, 0b11110000);
-        memory.set(FONT_BASE + 41, 0b10010000);
-        memory.set(FONT_BASE + 42, 0b11110000);
-        memory.set(FONT_BASE + 43, 0b10010000);
-        memory.set(FONT_BASE + 44, 0b11110000);
-
-        // 9
-        memory.set(FONT_BASE + 45, 0b11110000);
-        memory.set(FONT_BASE + 46, 0b10010000);
-        memory.set(FONT_BASE + 47, 0b11110000);
-        memory.set(FONT_BASE + 48, 0b00010000);
-        memory.set(FONT_BASE + 49, 0b11110000);
-
-        // A
-        memory.set(FONT_BASE + 50, 0b11110000);
-        memory.set(FONT_BASE + 51, 0b10010000);
-        memory.set(FONT_BASE + 52, 0b11110000);
-        memory.set(FONT_BASE + 53, 0b10010000);
-        memory.set(FONT_BASE + 54, 0b10010000);
-
-        // B
-        memory.set(FONT_BASE + 55, 0b11100000);
-        memory.set(FONT_BASE + 56, 0b10010000);
-        memory.set(FONT_BASE + 57, 0b11100000);
-        memory.set(FONT_BASE + 58, 0b10010000);
-        memory.set(FONT_BASE + 59, 0b11100000);
-
-        // C
-        memory.set(FONT_BASE + 60, 0b11110000);
-        memory.set(FONT_BASE + 61, 0b10000000);
-        memory.set(FONT_BASE + 62, 0b10000000);
-        memory.set(FONT_BASE + 63, 0b10000000);
-        memory.set(FONT_BASE + 64, 0b11110000);
-
-        // D
-        memory.set(FONT_BASE + 65, 0b11100000);
-        memory.set(FONT_BASE + 66, 0b10010000);
-        memory.set(FONT_BASE + 67, 0b10010000);
-        memory.set(FONT_BASE + 68, 0b10010000);
-        memory.set(FONT_BASE + 69, 0b11100000);
-
-        // E
-        memory.set(FONT_BASE + 70, 0b11110000);
-        memory.set(FONT_BASE + 71, 0b10000000);
-        memory.set(FONT_BASE + 72, 0b11110000);
-        memory.set(FONT_BASE + 73, 0b10000000);
-        memory.set(FONT_BASE + 74, 0b11110000);
-
-        // F
-        memory.set(FONT_BASE + 75, 0b11110000);
-        memory.set(FONT_BASE + 76, 0b10000000);
-        memory.set(FONT_BASE + 77, 0b11100000);
-        memory.set(FONT_BASE + 78, 0b10000000);
-        memory.set(FONT_BASE + 79, 0b10000000);
+        let font_base = FONT_BASE as usize;
+        memory.bytes[font_base..font_base + FONT.len()].copy_from_slice(&FONT);
+
+        let large_font_base = LARGE_FONT_BASE as usize;
+        memory.bytes[large_font_base..large_font_base + LARGE_FONT.len()]
+            .copy_from_slice(&LARGE_FONT);
 
         memory
     }
 }
+
+/// Memory is stored whole, preceded by its size
+impl Snapshot for Memory {
+    fn save(&self, writer: &mut StateWriter) {
+        writer.u64(self.bytes.len() as u64);
+        writer.bytes(&self.bytes);
+    }
+
+    fn restore(reader: &mut StateReader) -> Result<Self, Chip8Error> {
+        let size = reader.u64()?;
+
+        if size != MEMORY_SIZE as u64 && size != EXTENDED_MEMORY_SIZE as u64 {
+            return Err(Chip8Error::InvalidSaveState);
+        }
+
+        Ok(Memory {
+            bytes: reader.bytes(size as usize)?.to_vec(),
+            watchpoints: Vec::new(),
+            hit: Cell::new(None),
+        })
+    }
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Memory::new(MEMORY_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chip8::MemoryAccess;
+
+    #[test]
+    fn get_out_of_bounds() {
+        let memory = Memory::default();
+        assert_eq!(
+            memory.get(0x1000),
+            Err(Chip8Error::MemoryOutOfBounds { address: 0x1000 })
+        );
+    }
+
+    #[test]
+    fn set_out_of_bounds() {
+        let mut memory = Memory::default();
+        assert_eq!(
+            memory.set(0x1000, 1),
+            Err(Chip8Error::MemoryOutOfBounds { address: 0x1000 })
+        );
+    }
+
+    #[test]
+    fn range_out_of_bounds() {
+        let memory = Memory::default();
+        assert_eq!(
+            memory.range(0xFFE, 4),
+            Err(Chip8Error::MemoryOutOfBounds { address: 0x1000 })
+        );
+        assert_eq!(
+            memory.range(0x1234, 1),
+            Err(Chip8Error::MemoryOutOfBounds { address: 0x1234 })
+        );
+        assert_eq!(memory.range(0xFFE, 2).unwrap(), &[0, 0]);
+    }
+
+    #[test]
+    fn extended_memory() {
+        let mut memory = Memory::new(EXTENDED_MEMORY_SIZE);
+        memory.set(0xFFFF, 1).unwrap();
+        assert_eq!(memory.get(0xFFFF).unwrap(), 1);
+        assert_eq!(memory.range(0xFFFE, 2).unwrap(), &[0, 1]);
+        assert!(memory.range(0xFFFF, 2).is_err());
+    }
+
+    #[test]
+    fn watch_reads_and_writes() {
+        let mut memory = Memory::default();
+        memory.set_watchpoints(&[
+            Watchpoint::Memory {
+                range: 0x300..=0x303,
+                access: MemoryAccess::Write,
+            },
+            Watchpoint::Memory {
+                range: 0x400..=0x400,
+                access: MemoryAccess::Read,
+            },
+        ]);
+
+        memory.set(0x304, 1).unwrap();
+        assert_eq!(memory.get(0x303).unwrap(), 0);
+        assert_eq!(memory.peek(0x400).unwrap(), 0);
+        assert_eq!(memory.take_watchpoint_hit(), None);
+
+        memory.set(0x302, 7).unwrap();
+        memory.set(0x303, 8).unwrap();
+        assert_eq!(
+            memory.take_watchpoint_hit(),
+            Some(WatchpointHit::MemoryWrite {
+                address: 0x302,
+                old: 0,
+                new: 7
+            })
+        );
+        assert_eq!(memory.take_watchpoint_hit(), None);
+
+        memory.range(0x3FE, 4).unwrap();
+        assert_eq!(
+            memory.take_watchpoint_hit(),
+            Some(WatchpointHit::MemoryRead {
+                address: 0x400,
+                value: 0
+            })
+        );
+    }
+
+    #[test]
+    fn index_of_invalid_char() {
+        let memory = Memory::default();
+        assert_eq!(
+            memory.index_of_char(16),
+            Err(Chip8Error::InvalidFontDigit { digit: 16 })
+        );
+    }
+
+    #[test]
+    fn large_font_follows_small_font() {
+        let memory = Memory::default();
+        let index = memory.index_of_large_char(1).unwrap();
+        assert_eq!(index, 0x5A);
+        assert_eq!(memory.get(index).unwrap(), 0b00011000);
+        assert_eq!(memory.get(index + 9).unwrap(), 0b11111111);
+    }
+}
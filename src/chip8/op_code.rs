@@ -7,15 +7,115 @@ pub enum OpCode {
     /// Clear the display (`00E0`)
     Cls,
 
+    /// Return from a subroutine (`00EE`)
+    ///
+    /// Pops the return address from the top of the stack into the program counter.
+    Return,
+
+    /// Scroll the display down by the given number of pixels (`00Cn`)
+    ScrollDown(u8),
+
+    /// Scroll the display right by 4 pixels (`00FB`)
+    ScrollRight,
+
+    /// Scroll the display left by 4 pixels (`00FC`)
+    ScrollLeft,
+
+    /// Switch to the 64x32 low resolution mode, clearing the display (`00FE`)
+    LowResolution,
+
+    /// Switch to the SUPER-CHIP 128x64 high resolution mode, clearing the display (`00FF`)
+    HighResolution,
+
+    /// Call subroutine at location (`2nnn`)
+    ///
+    /// Pushes the current program counter onto the stack and jumps to the given location.
+    Call(u16),
+
+    /// Skip next instruction if the register equals the given value (`3xkk`)
+    SkipIfEqual(VRegister, u8),
+
+    /// Skip next instruction if the register is different from the given value (`4xkk`)
+    SkipIfNotEqual(VRegister, u8),
+
+    /// Skip next instruction if both registers are equal (`5xy0`)
+    SkipIfRegistersEqual(VRegister, VRegister),
+
+    /// Store the registers from the first to the second one in memory (`5xy2`)
+    ///
+    /// Values are stored starting at the address pointed at by the index register, which is left
+    /// unchanged. Registers are stored in reverse order if the first one comes after the second.
+    SaveRange(VRegister, VRegister),
+
+    /// Load the registers from the first to the second one from memory (`5xy3`)
+    ///
+    /// Values are loaded starting at the address pointed at by the index register, which is left
+    /// unchanged. Registers are loaded in reverse order if the first one comes after the second.
+    LoadRange(VRegister, VRegister),
+
     /// Set value of V register (`6XNN`)
     SetRegister(VRegister, u8),
 
+    /// Add value to V register (`7xkk`)
+    ///
+    /// The addition wraps around and does not affect VF.
+    AddValue(VRegister, u8),
+
+    /// Copy the value of the second register into the first one (`8xy0`)
+    CopyRegister(VRegister, VRegister),
+
+    /// Bitwise OR of both registers, stored in the first one (`8xy1`)
+    Or(VRegister, VRegister),
+
+    /// Bitwise AND of both registers, stored in the first one (`8xy2`)
+    And(VRegister, VRegister),
+
+    /// Bitwise XOR of both registers, stored in the first one (`8xy3`)
+    Xor(VRegister, VRegister),
+
+    /// Add both registers, storing the result in the first one (`8xy4`)
+    ///
+    /// VF is set to 1 if the addition overflowed, 0 otherwise.
+    Add(VRegister, VRegister),
+
+    /// Subtract the second register from the first one, storing the result in the first one
+    /// (`8xy5`)
+    ///
+    /// VF is set to 1 if there was no borrow, 0 otherwise.
+    Sub(VRegister, VRegister),
+
+    /// Shift right by one, storing the result in the first register (`8xy6`)
+    ///
+    /// Depending on the quirks in use the value shifted is either the one in the second register or
+    /// the one in the first register. VF is set to the bit that was shifted out.
+    ShiftRight(VRegister, VRegister),
+
+    /// Subtract the first register from the second one, storing the result in the first one
+    /// (`8xy7`)
+    ///
+    /// VF is set to 1 if there was no borrow, 0 otherwise.
+    SubNegated(VRegister, VRegister),
+
+    /// Shift left by one, storing the result in the first register (`8xyE`)
+    ///
+    /// Depending on the quirks in use the value shifted is either the one in the second register or
+    /// the one in the first register. VF is set to the bit that was shifted out.
+    ShiftLeft(VRegister, VRegister),
+
+    /// Skip next instruction if both registers are different (`9xy0`)
+    SkipIfRegistersNotEqual(VRegister, VRegister),
+
     /// Set value of index register (`Annn`)
     LoadIndex(u16),
 
-    /// Jump to location (`Bnnn`)
+    /// Jump to location (`1nnn`)
     Jump(u16),
 
+    /// Jump to location plus the value of V0 (`Bnnn`)
+    ///
+    /// Some interpreters treat it as `Bxnn` instead, adding the value of Vx.
+    JumpOffset(u16),
+
     /// Set a register to a random value (`Cxkk`)
     ///
     /// The randomly generated value is and-ed with the given `kk` value.
@@ -25,16 +125,61 @@ pub enum OpCode {
     ///
     /// Draws an n-bytes sprite at position (x, y). The sprite is read from the memory address
     /// pointed at by the index register. Sprites are XORed onto the screen. If a pixel goes from
-    /// to unset, VF is set to 1, otherwise it is set to 0. Sprites wrap around if they go through
-    /// the edge of the screen.
+    /// to unset, VF is set to 1, otherwise it is set to 0. Depending on the quirks in use, sprites
+    /// either wrap around or get clipped if they go through the edge of the screen. A length of 0
+    /// draws a SUPER-CHIP 16x16 sprite made of 32 bytes. With several XO-CHIP planes selected, one
+    /// sprite is read for each of them, one after the other.
     Draw((VRegister, VRegister), u8),
 
+    /// Skip next instruction if the key with the value of the given register is pressed (`Ex9E`)
+    SkipIfKeyPressed(VRegister),
+
+    /// Skip next instruction if the key with the value of the given register is not pressed
+    /// (`ExA1`)
+    SkipIfKeyNotPressed(VRegister),
+
+    /// Store the value of the delay timer in the given V register (`Fx07`)
+    LoadDelayTimer(VRegister),
+
+    /// Select the XO-CHIP bitplanes affected by drawing, clearing and scrolling (`Fn01`)
+    ///
+    /// Each bit of `n` selects one plane.
+    SelectPlanes(u8),
+
+    /// Set value of index register to a 16 bit address (`F000 nnnn`)
+    ///
+    /// This is the only instruction taking 4 bytes: the address is read from the two bytes
+    /// following the opcode.
+    LoadLongIndex,
+
+    /// Load the 16 byte XO-CHIP audio pattern starting at the address in the index register
+    /// (`F002`)
+    LoadAudioPattern,
+
+    /// Set the XO-CHIP audio pitch register to the value of the given V register (`Fx3A`)
+    SetPitch(VRegister),
+
     /// Wait for key press and store the value of the key in the given V register (`Fx0A`)
     WaitForKeyPress(VRegister),
 
+    /// Set the delay timer to the value of the given V register (`Fx15`)
+    SetDelayTimer(VRegister),
+
+    /// Set the sound timer to the value of the given V register (`Fx18`)
+    ///
+    /// The buzzer sounds for as long as the sound timer is non-zero.
+    SetSoundTimer(VRegister),
+
+    /// Add the value of the given V register to the index register (`Fx1E`)
+    AddIndex(VRegister),
+
     /// Point index to the character corresponding to the value of the given register (`Fx29`)
     LoadCharacter(VRegister),
 
+    /// Point index to the large character corresponding to the value of the given register
+    /// (`Fx30`)
+    LoadLargeCharacter(VRegister),
+
     /// Load decimal representation in memory (`Fx33`)
     ///
     /// Takes the decimal representation of the value in the given register and stores it in memory,
@@ -42,11 +187,23 @@ pub enum OpCode {
     /// the tens and the following one for the ones.
     LoadDecimal(VRegister),
 
+    /// Stores data from V registers up to the given one into memory (`Fx55`)
+    ///
+    /// Store values of the V registers up until the given one in memory, starting at the address
+    /// pointed at by the index register. Some interpreters increment the index register as they go.
+    Store(VRegister),
+
     /// Loads data from memory to V registers up to the given one (`Fx65`)
     ///
     /// Load values from memory, starting at the address pointed at by the index register, to the V
-    /// registers up until the given one.
+    /// registers up until the given one. Some interpreters increment the index register as they go.
     Fill(VRegister),
+
+    /// Saves V registers up to the given one into the RPL user flags (`Fx75`)
+    SaveFlags(VRegister),
+
+    /// Loads V registers up to the given one from the RPL user flags (`Fx85`)
+    LoadFlags(VRegister),
 }
 
 impl TryFrom<[u8; 2]> for OpCode {
@@ -59,44 +216,210 @@ impl TryFrom<[u8; 2]> for OpCode {
             // OpCode::Cls
             [0x00, 0xE0] => Ok(OpCode::Cls),
 
+            // OpCode::Return
+            [0x00, 0xEE] => Ok(OpCode::Return),
+
+            // OpCode::ScrollDown
+            [0x00, lsb] if (0xC0..=0xCF).contains(&lsb) => Ok(OpCode::ScrollDown(lsb & 0x0F)),
+
+            // OpCode::ScrollRight
+            [0x00, 0xFB] => Ok(OpCode::ScrollRight),
+
+            // OpCode::ScrollLeft
+            [0x00, 0xFC] => Ok(OpCode::ScrollLeft),
+
+            // OpCode::LowResolution
+            [0x00, 0xFE] => Ok(OpCode::LowResolution),
+
+            // OpCode::HighResolution
+            [0x00, 0xFF] => Ok(OpCode::HighResolution),
+
             // OpCode::Jump
             [msb, _] if (0x10..=0x1F).contains(&msb) => Ok(OpCode::Jump(0x0FFF & pack_u8(bytes))),
 
+            // OpCode::Call
+            [msb, _] if (0x20..=0x2F).contains(&msb) => Ok(OpCode::Call(0x0FFF & pack_u8(bytes))),
+
+            // OpCode::SkipIfEqual
+            [msb, lsb] if (0x30..=0x3F).contains(&msb) => {
+                let register = VRegister::try_from(msb & 0x0F).unwrap();
+                Ok(OpCode::SkipIfEqual(register, lsb))
+            }
+
+            // OpCode::SkipIfNotEqual
+            [msb, lsb] if (0x40..=0x4F).contains(&msb) => {
+                let register = VRegister::try_from(msb & 0x0F).unwrap();
+                Ok(OpCode::SkipIfNotEqual(register, lsb))
+            }
+
+            // OpCode::SkipIfRegistersEqual
+            [msb, lsb] if (0x50..=0x5F).contains(&msb) && lsb & 0x0F == 0 => {
+                let register_x = VRegister::try_from(msb & 0x0F).unwrap();
+                let register_y = VRegister::try_from((lsb & 0xF0) >> 4).unwrap();
+                Ok(OpCode::SkipIfRegistersEqual(register_x, register_y))
+            }
+
+            // OpCode::SaveRange
+            [msb, lsb] if (0x50..=0x5F).contains(&msb) && lsb & 0x0F == 2 => {
+                let register_x = VRegister::try_from(msb & 0x0F).unwrap();
+                let register_y = VRegister::try_from((lsb & 0xF0) >> 4).unwrap();
+                Ok(OpCode::SaveRange(register_x, register_y))
+            }
+
+            // OpCode::LoadRange
+            [msb, lsb] if (0x50..=0x5F).contains(&msb) && lsb & 0x0F == 3 => {
+                let register_x = VRegister::try_from(msb & 0x0F).unwrap();
+                let register_y = VRegister::try_from((lsb & 0xF0) >> 4).unwrap();
+                Ok(OpCode::LoadRange(register_x, register_y))
+            }
+
+            // OpCode::SkipIfRegistersNotEqual
+            [msb, lsb] if (0x90..=0x9F).contains(&msb) && lsb & 0x0F == 0 => {
+                let register_x = VRegister::try_from(msb & 0x0F).unwrap();
+                let register_y = VRegister::try_from((lsb & 0xF0) >> 4).unwrap();
+                Ok(OpCode::SkipIfRegistersNotEqual(register_x, register_y))
+            }
+
             // OpCode::LoadIndex
             [msb, _] if (0xA0..=0xAF).contains(&msb) => {
                 Ok(OpCode::LoadIndex(pack_u8(bytes) & 0x0FFF))
             }
 
+            // OpCode::JumpOffset
+            [msb, _] if (0xB0..=0xBF).contains(&msb) => {
+                Ok(OpCode::JumpOffset(0x0FFF & pack_u8(bytes)))
+            }
+
             // OpCode::Random
             [msb, lsb] if (0xC0..=0xCF).contains(&msb) => {
                 let register = VRegister::try_from(msb & 0x0F).unwrap();
                 Ok(OpCode::Random(register, lsb))
             }
 
+            // OpCode::SkipIfKeyPressed
+            [msb, 0x9E] if (0xE0..=0xEF).contains(&msb) => {
+                let register = VRegister::try_from(msb & 0x0F).unwrap();
+                Ok(OpCode::SkipIfKeyPressed(register))
+            }
+
+            // OpCode::SkipIfKeyNotPressed
+            [msb, 0xA1] if (0xE0..=0xEF).contains(&msb) => {
+                let register = VRegister::try_from(msb & 0x0F).unwrap();
+                Ok(OpCode::SkipIfKeyNotPressed(register))
+            }
+
+            // OpCode::LoadLongIndex
+            [0xF0, 0x00] => Ok(OpCode::LoadLongIndex),
+
+            // OpCode::LoadAudioPattern
+            [0xF0, 0x02] => Ok(OpCode::LoadAudioPattern),
+
+            // OpCode::SelectPlanes
+            [msb, 0x01] if (0xF0..=0xFF).contains(&msb) => Ok(OpCode::SelectPlanes(msb & 0x0F)),
+
+            // OpCode::LoadDelayTimer
+            [msb, 0x07] if (0xF0..=0xFF).contains(&msb) => {
+                let register = VRegister::try_from(msb & 0x0F).unwrap();
+                Ok(OpCode::LoadDelayTimer(register))
+            }
+
+            // OpCode::SetDelayTimer
+            [msb, 0x15] if (0xF0..=0xFF).contains(&msb) => {
+                let register = VRegister::try_from(msb & 0x0F).unwrap();
+                Ok(OpCode::SetDelayTimer(register))
+            }
+
+            // OpCode::SetSoundTimer
+            [msb, 0x18] if (0xF0..=0xFF).contains(&msb) => {
+                let register = VRegister::try_from(msb & 0x0F).unwrap();
+                Ok(OpCode::SetSoundTimer(register))
+            }
+
+            // OpCode::AddIndex
+            [msb, 0x1E] if (0xF0..=0xFF).contains(&msb) => {
+                let register = VRegister::try_from(msb & 0x0F).unwrap();
+                Ok(OpCode::AddIndex(register))
+            }
+
             // OpCode::LoadCharacter
             [msb, 0x29] if (0xF0..=0xFF).contains(&msb) => {
                 let register = VRegister::try_from(msb & 0x0F).unwrap();
                 Ok(OpCode::LoadCharacter(register))
             }
 
+            // OpCode::LoadLargeCharacter
+            [msb, 0x30] if (0xF0..=0xFF).contains(&msb) => {
+                let register = VRegister::try_from(msb & 0x0F).unwrap();
+                Ok(OpCode::LoadLargeCharacter(register))
+            }
+
+            // OpCode::SetPitch
+            [msb, 0x3A] if (0xF0..=0xFF).contains(&msb) => {
+                let register = VRegister::try_from(msb & 0x0F).unwrap();
+                Ok(OpCode::SetPitch(register))
+            }
+
             // OpCode::LoadDecimal
             [msb, 0x33] if (0xF0..=0xFF).contains(&msb) => {
                 let register = VRegister::try_from(msb & 0x0F).unwrap();
                 Ok(OpCode::LoadDecimal(register))
             }
 
+            // OpCode::Store
+            [msb, 0x55] if (0xF0..=0xFF).contains(&msb) => {
+                let register = VRegister::try_from(msb & 0x0F).unwrap();
+                Ok(OpCode::Store(register))
+            }
+
             // OpCode::Fill
             [msb, 0x65] if (0xF0..=0xFF).contains(&msb) => {
                 let register = VRegister::try_from(msb & 0x0F).unwrap();
                 Ok(OpCode::Fill(register))
             }
 
+            // OpCode::SaveFlags
+            [msb, 0x75] if (0xF0..=0xFF).contains(&msb) => {
+                let register = VRegister::try_from(msb & 0x0F).unwrap();
+                Ok(OpCode::SaveFlags(register))
+            }
+
+            // OpCode::LoadFlags
+            [msb, 0x85] if (0xF0..=0xFF).contains(&msb) => {
+                let register = VRegister::try_from(msb & 0x0F).unwrap();
+                Ok(OpCode::LoadFlags(register))
+            }
+
             // OpCode::SetRegister
             [msb, lsb] if (0x60..=0x6F).contains(&msb) => {
                 let register = VRegister::try_from(msb & 0x0F).unwrap();
                 Ok(OpCode::SetRegister(register, lsb))
             }
 
+            // OpCode::AddValue
+            [msb, lsb] if (0x70..=0x7F).contains(&msb) => {
+                let register = VRegister::try_from(msb & 0x0F).unwrap();
+                Ok(OpCode::AddValue(register, lsb))
+            }
+
+            // Register to register operations
+            [msb, lsb] if (0x80..=0x8F).contains(&msb) => {
+                let register_x = VRegister::try_from(msb & 0x0F).unwrap();
+                let register_y = VRegister::try_from((lsb & 0xF0) >> 4).unwrap();
+
+                match lsb & 0x0F {
+                    0x0 => Ok(OpCode::CopyRegister(register_x, register_y)),
+                    0x1 => Ok(OpCode::Or(register_x, register_y)),
+                    0x2 => Ok(OpCode::And(register_x, register_y)),
+                    0x3 => Ok(OpCode::Xor(register_x, register_y)),
+                    0x4 => Ok(OpCode::Add(register_x, register_y)),
+                    0x5 => Ok(OpCode::Sub(register_x, register_y)),
+                    0x6 => Ok(OpCode::ShiftRight(register_x, register_y)),
+                    0x7 => Ok(OpCode::SubNegated(register_x, register_y)),
+                    0xE => Ok(OpCode::ShiftLeft(register_x, register_y)),
+                    _ => Err(format!("Invalid OpCode {:#02x}{:02x}", bytes[0], bytes[1])),
+                }
+            }
+
             // OpCode::Draw
             [msb, lsb] if (0xD0..=0xDF).contains(&msb) => {
                 let register_x = VRegister::try_from(msb & 0x0F).unwrap();
@@ -132,24 +455,251 @@ mod tests {
         assert!(matches!(op_code, OpCode::Cls));
     }
 
+    #[test]
+    fn parse_scroll_down() {
+        let op_code = OpCode::try_from([0x00, 0xC5]).unwrap();
+        assert!(matches!(op_code, OpCode::ScrollDown(5)));
+    }
+
+    #[test]
+    fn parse_scroll_right() {
+        let op_code = OpCode::try_from([0x00, 0xFB]).unwrap();
+        assert!(matches!(op_code, OpCode::ScrollRight));
+    }
+
+    #[test]
+    fn parse_scroll_left() {
+        let op_code = OpCode::try_from([0x00, 0xFC]).unwrap();
+        assert!(matches!(op_code, OpCode::ScrollLeft));
+    }
+
+    #[test]
+    fn parse_low_resolution() {
+        let op_code = OpCode::try_from([0x00, 0xFE]).unwrap();
+        assert!(matches!(op_code, OpCode::LowResolution));
+    }
+
+    #[test]
+    fn parse_high_resolution() {
+        let op_code = OpCode::try_from([0x00, 0xFF]).unwrap();
+        assert!(matches!(op_code, OpCode::HighResolution));
+    }
+
     #[test]
     fn parse_jump() {
         let op_code = OpCode::try_from([0x10, 0xAA]).unwrap();
         assert!(matches!(op_code, OpCode::Jump(0x00AA)));
     }
 
+    #[test]
+    fn parse_return() {
+        let op_code = OpCode::try_from([0x00, 0xEE]).unwrap();
+        assert!(matches!(op_code, OpCode::Return));
+    }
+
+    #[test]
+    fn parse_call() {
+        let op_code = OpCode::try_from([0x23, 0x45]).unwrap();
+        assert!(matches!(op_code, OpCode::Call(0x0345)));
+    }
+
+    #[test]
+    fn parse_skip_if_equal() {
+        let op_code = OpCode::try_from([0x3A, 0x12]).unwrap();
+        assert!(matches!(op_code, OpCode::SkipIfEqual(VRegister::VA, 0x12)));
+    }
+
+    #[test]
+    fn parse_skip_if_not_equal() {
+        let op_code = OpCode::try_from([0x4A, 0x12]).unwrap();
+        assert!(matches!(
+            op_code,
+            OpCode::SkipIfNotEqual(VRegister::VA, 0x12)
+        ));
+    }
+
+    #[test]
+    fn parse_skip_if_registers_equal() {
+        let op_code = OpCode::try_from([0x51, 0x20]).unwrap();
+        assert!(matches!(
+            op_code,
+            OpCode::SkipIfRegistersEqual(VRegister::V1, VRegister::V2)
+        ));
+    }
+
+    #[test]
+    fn parse_save_range() {
+        let op_code = OpCode::try_from([0x51, 0x32]).unwrap();
+        assert!(matches!(
+            op_code,
+            OpCode::SaveRange(VRegister::V1, VRegister::V3)
+        ));
+    }
+
+    #[test]
+    fn parse_load_range() {
+        let op_code = OpCode::try_from([0x51, 0x33]).unwrap();
+        assert!(matches!(
+            op_code,
+            OpCode::LoadRange(VRegister::V1, VRegister::V3)
+        ));
+    }
+
+    #[test]
+    fn parse_skip_if_registers_not_equal() {
+        let op_code = OpCode::try_from([0x91, 0x20]).unwrap();
+        assert!(matches!(
+            op_code,
+            OpCode::SkipIfRegistersNotEqual(VRegister::V1, VRegister::V2)
+        ));
+    }
+
+    #[test]
+    fn parse_skip_if_key_pressed() {
+        let op_code = OpCode::try_from([0xE3, 0x9E]).unwrap();
+        assert!(matches!(op_code, OpCode::SkipIfKeyPressed(VRegister::V3)));
+    }
+
+    #[test]
+    fn parse_skip_if_key_not_pressed() {
+        let op_code = OpCode::try_from([0xE3, 0xA1]).unwrap();
+        assert!(matches!(
+            op_code,
+            OpCode::SkipIfKeyNotPressed(VRegister::V3)
+        ));
+    }
+
+    #[test]
+    fn parse_add_value() {
+        let op_code = OpCode::try_from([0x73, 0x42]).unwrap();
+        assert!(matches!(op_code, OpCode::AddValue(VRegister::V3, 0x42)));
+    }
+
+    #[test]
+    fn parse_copy_register() {
+        let op_code = OpCode::try_from([0x81, 0x20]).unwrap();
+        assert!(matches!(
+            op_code,
+            OpCode::CopyRegister(VRegister::V1, VRegister::V2)
+        ));
+    }
+
+    #[test]
+    fn parse_or() {
+        let op_code = OpCode::try_from([0x81, 0x21]).unwrap();
+        assert!(matches!(op_code, OpCode::Or(VRegister::V1, VRegister::V2)));
+    }
+
+    #[test]
+    fn parse_and() {
+        let op_code = OpCode::try_from([0x81, 0x22]).unwrap();
+        assert!(matches!(op_code, OpCode::And(VRegister::V1, VRegister::V2)));
+    }
+
+    #[test]
+    fn parse_xor() {
+        let op_code = OpCode::try_from([0x81, 0x23]).unwrap();
+        assert!(matches!(op_code, OpCode::Xor(VRegister::V1, VRegister::V2)));
+    }
+
+    #[test]
+    fn parse_add() {
+        let op_code = OpCode::try_from([0x8A, 0xB4]).unwrap();
+        assert!(matches!(op_code, OpCode::Add(VRegister::VA, VRegister::VB)));
+    }
+
+    #[test]
+    fn parse_sub() {
+        let op_code = OpCode::try_from([0x8A, 0xB5]).unwrap();
+        assert!(matches!(op_code, OpCode::Sub(VRegister::VA, VRegister::VB)));
+    }
+
+    #[test]
+    fn parse_shift_right() {
+        let op_code = OpCode::try_from([0x8A, 0xB6]).unwrap();
+        assert!(matches!(
+            op_code,
+            OpCode::ShiftRight(VRegister::VA, VRegister::VB)
+        ));
+    }
+
+    #[test]
+    fn parse_sub_negated() {
+        let op_code = OpCode::try_from([0x8A, 0xB7]).unwrap();
+        assert!(matches!(
+            op_code,
+            OpCode::SubNegated(VRegister::VA, VRegister::VB)
+        ));
+    }
+
+    #[test]
+    fn parse_shift_left() {
+        let op_code = OpCode::try_from([0x8A, 0xBE]).unwrap();
+        assert!(matches!(
+            op_code,
+            OpCode::ShiftLeft(VRegister::VA, VRegister::VB)
+        ));
+    }
+
+    #[test]
+    fn parse_invalid_register_operation() {
+        assert!(OpCode::try_from([0x8A, 0xB8]).is_err());
+    }
+
     #[test]
     fn parse_load_index() {
         let op_code = OpCode::try_from([0xAB, 0xCD]).unwrap();
         assert!(matches!(op_code, OpCode::LoadIndex(0x0BCD)));
     }
 
+    #[test]
+    fn parse_jump_offset() {
+        let op_code = OpCode::try_from([0xB1, 0x23]).unwrap();
+        assert!(matches!(op_code, OpCode::JumpOffset(0x0123)));
+    }
+
     #[test]
     fn parse_random_byte() {
         let op_code = OpCode::try_from([0xc2, 0x12]).unwrap();
         assert!(matches!(op_code, OpCode::Random(VRegister::V2, 0x12)));
     }
 
+    #[test]
+    fn parse_load_long_index() {
+        let op_code = OpCode::try_from([0xF0, 0x00]).unwrap();
+        assert!(matches!(op_code, OpCode::LoadLongIndex));
+    }
+
+    #[test]
+    fn parse_select_planes() {
+        let op_code = OpCode::try_from([0xF3, 0x01]).unwrap();
+        assert!(matches!(op_code, OpCode::SelectPlanes(3)));
+    }
+
+    #[test]
+    fn parse_load_delay_timer() {
+        let op_code = OpCode::try_from([0xF4, 0x07]).unwrap();
+        assert!(matches!(op_code, OpCode::LoadDelayTimer(VRegister::V4)));
+    }
+
+    #[test]
+    fn parse_set_delay_timer() {
+        let op_code = OpCode::try_from([0xF4, 0x15]).unwrap();
+        assert!(matches!(op_code, OpCode::SetDelayTimer(VRegister::V4)));
+    }
+
+    #[test]
+    fn parse_set_sound_timer() {
+        let op_code = OpCode::try_from([0xF4, 0x18]).unwrap();
+        assert!(matches!(op_code, OpCode::SetSoundTimer(VRegister::V4)));
+    }
+
+    #[test]
+    fn parse_add_index() {
+        let op_code = OpCode::try_from([0xF5, 0x1E]).unwrap();
+        assert!(matches!(op_code, OpCode::AddIndex(VRegister::V5)));
+    }
+
     #[test]
     fn parse_load_decimal() {
         let op_code = OpCode::try_from([0xf2, 0x33]).unwrap();
@@ -164,4 +714,46 @@ mod tests {
             OpCode::Draw((VRegister::V4, VRegister::V5), 0x8)
         ));
     }
+
+    #[test]
+    fn parse_store() {
+        let op_code = OpCode::try_from([0xF7, 0x55]).unwrap();
+        assert!(matches!(op_code, OpCode::Store(VRegister::V7)));
+    }
+
+    #[test]
+    fn parse_fill() {
+        let op_code = OpCode::try_from([0xF7, 0x65]).unwrap();
+        assert!(matches!(op_code, OpCode::Fill(VRegister::V7)));
+    }
+
+    #[test]
+    fn parse_load_large_character() {
+        let op_code = OpCode::try_from([0xF7, 0x30]).unwrap();
+        assert!(matches!(op_code, OpCode::LoadLargeCharacter(VRegister::V7)));
+    }
+
+    #[test]
+    fn parse_load_audio_pattern() {
+        let op_code = OpCode::try_from([0xF0, 0x02]).unwrap();
+        assert!(matches!(op_code, OpCode::LoadAudioPattern));
+    }
+
+    #[test]
+    fn parse_set_pitch() {
+        let op_code = OpCode::try_from([0xF4, 0x3A]).unwrap();
+        assert!(matches!(op_code, OpCode::SetPitch(VRegister::V4)));
+    }
+
+    #[test]
+    fn parse_save_flags() {
+        let op_code = OpCode::try_from([0xF7, 0x75]).unwrap();
+        assert!(matches!(op_code, OpCode::SaveFlags(VRegister::V7)));
+    }
+
+    #[test]
+    fn parse_load_flags() {
+        let op_code = OpCode::try_from([0xF7, 0x85]).unwrap();
+        assert!(matches!(op_code, OpCode::LoadFlags(VRegister::V7)));
+    }
 }
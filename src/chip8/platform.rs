@@ -0,0 +1,32 @@
+use super::{EXTENDED_MEMORY_SIZE, MEMORY_SIZE};
+
+/// Machine a ROM is written for, which determines how much memory it has
+///
+/// Differences in behaviour between interpreters for the same machine are covered by `Quirks`
+/// instead.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum Platform {
+    /// CHIP-8 and SUPER-CHIP, with 4 KiB of memory
+    #[default]
+    Chip8,
+
+    /// XO-CHIP, with the whole 64 KiB address space as memory
+    XoChip,
+}
+
+impl Platform {
+    pub fn memory_size(self) -> usize {
+        match self {
+            Platform::Chip8 => MEMORY_SIZE,
+            Platform::XoChip => EXTENDED_MEMORY_SIZE,
+        }
+    }
+
+    /// Number of RPL user flags `Fx75` and `Fx85` can use, SUPER-CHIP limiting them to V0-V7
+    pub fn rpl_flag_count(self) -> usize {
+        match self {
+            Platform::Chip8 => 8,
+            Platform::XoChip => 16,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+/// Behaviours that differ between CHIP-8 implementations
+///
+/// ROMs are usually written against a specific interpreter and rely on its particular behaviour
+/// for a handful of instructions. Use one of the presets to match the platform a ROM targets.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Quirks {
+    /// `8xy6` and `8xyE` shift Vy into Vx instead of shifting Vx in place
+    pub shift_uses_vy: bool,
+
+    /// `Fx55` and `Fx65` leave the index register pointing past the last address accessed
+    pub load_store_increments_index: bool,
+
+    /// `Bnnn` behaves as `Bxnn`, jumping to `xnn` plus the value of Vx instead of V0
+    pub jump_with_vx: bool,
+
+    /// `8xy1`, `8xy2` and `8xy3` reset VF to 0
+    pub logic_resets_vf: bool,
+
+    /// Sprites are clipped at the edges of the screen instead of wrapping around
+    pub clip_sprites: bool,
+
+    /// `Dxyn` waits for the next 60 Hz tick before executing further instructions
+    pub display_wait: bool,
+}
+
+impl Quirks {
+    /// The original interpreter on the RCA COSMAC VIP
+    pub const COSMAC_VIP: Quirks = Quirks {
+        shift_uses_vy: true,
+        load_store_increments_index: true,
+        jump_with_vx: false,
+        logic_resets_vf: true,
+        clip_sprites: true,
+        display_wait: true,
+    };
+
+    /// SUPER-CHIP 1.1 on the HP48 calculators
+    pub const SCHIP: Quirks = Quirks {
+        shift_uses_vy: false,
+        load_store_increments_index: false,
+        jump_with_vx: true,
+        logic_resets_vf: false,
+        clip_sprites: true,
+        display_wait: false,
+    };
+
+    /// XO-CHIP as specified by Octo
+    pub const XO_CHIP: Quirks = Quirks {
+        shift_uses_vy: true,
+        load_store_increments_index: true,
+        jump_with_vx: false,
+        logic_resets_vf: false,
+        clip_sprites: false,
+        display_wait: false,
+    };
+
+    /// Octo's defaults, which it also uses for plain CHIP-8 programs and which match XO-CHIP
+    pub const OCTO: Quirks = Quirks::XO_CHIP;
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks::COSMAC_VIP
+    }
+}
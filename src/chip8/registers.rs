@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 mod registers;
 
 pub use self::registers::{all_registers, Registers, VRegister};
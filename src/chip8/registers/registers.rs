@@ -1,17 +1,65 @@
 use std::convert::TryFrom;
 
+use crate::chip8::{Chip8Error, Snapshot, StateReader, StateWriter, Watchpoint, WatchpointHit};
+
 #[derive(Default)]
-pub struct Registers([u8; 16]);
+pub struct Registers {
+    values: [u8; 16],
+    watchpoints: Vec<Watchpoint>,
+    /// First watched change since the last `take_watchpoint_hit`
+    hit: Option<WatchpointHit>,
+}
 
 impl Registers {
     pub fn get(&self, register: VRegister) -> u8 {
         let index: usize = register.into();
-        self.0[index]
+        self.values[index]
     }
 
     pub fn set(&mut self, register: VRegister, value: u8) {
         let index: usize = register.into();
-        self.0[index] = value;
+        let old = self.values[index];
+        self.values[index] = value;
+
+        if self.hit.is_none()
+            && self
+                .watchpoints
+                .iter()
+                .any(|watchpoint| watchpoint.is_register_write(register, old, value))
+        {
+            self.hit = Some(WatchpointHit::RegisterWrite {
+                register,
+                old,
+                new: value,
+            });
+        }
+    }
+
+    /// Replaces the watchpoints checked on every write, keeping only the register ones
+    pub fn set_watchpoints(&mut self, watchpoints: &[Watchpoint]) {
+        self.watchpoints = watchpoints
+            .iter()
+            .filter(|watchpoint| !matches!(watchpoint, Watchpoint::Memory { .. }))
+            .cloned()
+            .collect();
+    }
+
+    /// Returns the first watched change since the last call
+    pub fn take_watchpoint_hit(&mut self) -> Option<WatchpointHit> {
+        self.hit.take()
+    }
+}
+
+impl Snapshot for Registers {
+    fn save(&self, writer: &mut StateWriter) {
+        writer.bytes(&self.values);
+    }
+
+    fn restore(reader: &mut StateReader) -> Result<Self, Chip8Error> {
+        Ok(Registers {
+            values: reader.array()?,
+            ..Registers::default()
+        })
     }
 }
 
@@ -0,0 +1,283 @@
+use std::collections::VecDeque;
+
+use super::{Chip8Error, StateWriter};
+
+/// History of save states, one per frame, for stepping execution backwards
+///
+/// Only the latest state is kept whole. Each older one is stored as the difference with the state
+/// that followed it: the two are XORed together, which leaves zeros wherever nothing changed, and
+/// runs of zeros are then skipped over. Frames usually only touch a handful of bytes, so this
+/// takes a tiny fraction of the space of full states.
+pub struct RewindBuffer {
+    capacity: usize,
+    latest: Option<Vec<u8>>,
+    /// The state before `latest`, kept to write the next one into instead of allocating
+    spare: Vec<u8>,
+    /// Deltas turning each state into the one before it, oldest first
+    deltas: VecDeque<Vec<u8>>,
+}
+
+impl RewindBuffer {
+    /// Creates a buffer able to go back up to `capacity` frames
+    pub fn new(capacity: usize) -> RewindBuffer {
+        RewindBuffer {
+            capacity,
+            latest: None,
+            spare: Vec::new(),
+            deltas: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Adds the state of a new frame, written by `save`, dropping the oldest one once the buffer is
+    /// full
+    pub fn record(&mut self, save: impl FnOnce(&mut StateWriter)) {
+        let mut writer = StateWriter::reusing(std::mem::take(&mut self.spare));
+        save(&mut writer);
+        let state = writer.into_bytes();
+
+        if let Some(previous) = self.latest.take() {
+            self.deltas.push_back(delta(&state, &previous));
+
+            if self.deltas.len() > self.capacity {
+                self.deltas.pop_front();
+            }
+
+            self.spare = previous;
+        }
+
+        self.latest = Some(state);
+    }
+
+    /// Goes back up to `frames` frames from the latest state, forgetting the frames in between.
+    /// Returns the state reached and how many frames were actually rewound, or `None` when nothing
+    /// was recorded.
+    pub fn rewind(&mut self, frames: usize) -> Result<Option<(&[u8], usize)>, Chip8Error> {
+        let mut state = match self.latest.take() {
+            Some(state) => state,
+            None => return Ok(None),
+        };
+        let mut rewound = 0;
+
+        while rewound < frames {
+            match self.deltas.pop_back() {
+                Some(delta) => {
+                    if apply_delta(&mut state, &delta).is_none() {
+                        // The state may be half updated, so none of the history can be trusted
+                        self.clear();
+                        return Err(Chip8Error::InvalidSaveState);
+                    }
+                }
+                None => break,
+            }
+
+            rewound += 1;
+        }
+
+        let state = self.latest.insert(state);
+        Ok(Some((state, rewound)))
+    }
+
+    /// Number of frames that can currently be rewound
+    pub fn frames(&self) -> usize {
+        self.deltas.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+    }
+}
+
+/// Encodes how to get from `from` back to `to`: the length of `to`, then pairs of a count of
+/// unchanged bytes to skip and a run of bytes to XOR in
+fn delta(from: &[u8], to: &[u8]) -> Vec<u8> {
+    let length = from.len().max(to.len());
+    let byte_at = |state: &[u8], index: usize| state.get(index).copied().unwrap_or(0);
+    let xor = |index: usize| byte_at(from, index) ^ byte_at(to, index);
+
+    let mut delta = Vec::new();
+    write_varint(&mut delta, to.len());
+
+    let mut position = 0;
+    while position < length {
+        let unchanged = (position..length)
+            .take_while(|index| xor(*index) == 0)
+            .count();
+        position += unchanged;
+
+        if position == length {
+            break;
+        }
+
+        // A single unchanged byte costs less inside a run than as the start of a new pair
+        let mut end = position;
+        while end < length {
+            let changed = xor(end) != 0 || (end + 1 < length && xor(end + 1) != 0);
+            if !changed {
+                break;
+            }
+
+            end += 1;
+        }
+
+        write_varint(&mut delta, unchanged);
+        write_varint(&mut delta, end - position);
+        delta.extend((position..end).map(xor));
+        position = end;
+    }
+
+    delta
+}
+
+/// Applies a delta made by `delta`, returning `None` when it is malformed
+fn apply_delta(state: &mut Vec<u8>, delta: &[u8]) -> Option<()> {
+    let mut delta = delta;
+    let length = read_varint(&mut delta)?;
+
+    let mut position: usize = 0;
+    while !delta.is_empty() {
+        position = position.checked_add(read_varint(&mut delta)?)?;
+        let changed = read_varint(&mut delta)?;
+        let end = position.checked_add(changed)?;
+        let xor = delta.get(..changed)?;
+
+        // Bytes past the end of the shorter state count as zeros
+        if end > state.len().max(length) {
+            return None;
+        } else if end > state.len() {
+            state.resize(end, 0);
+        }
+
+        for (byte, xor) in state[position..end].iter_mut().zip(xor) {
+            *byte ^= xor;
+        }
+
+        delta = &delta[changed..];
+        position = end;
+    }
+
+    state.resize(length, 0);
+    Some(())
+}
+
+/// LEB128, 7 bits per byte with the high bit set on all but the last one
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+
+    out.push(value as u8);
+}
+
+/// Reads a value written by `write_varint`, returning `None` when it is truncated or too large
+fn read_varint(data: &mut &[u8]) -> Option<usize> {
+    let mut value: usize = 0;
+    let mut shift = 0;
+
+    loop {
+        let (&byte, rest) = data.split_first()?;
+        *data = rest;
+        let bits = (byte & 0x7F) as usize;
+
+        if shift >= usize::BITS || (bits << shift) >> shift != bits {
+            return None;
+        }
+
+        value |= bits << shift;
+
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_round_trip() {
+        let from = vec![1, 2, 3, 0, 0, 0, 0, 9, 9, 9];
+        let to = vec![1, 2, 4, 0, 5, 0, 0, 9, 9, 8];
+        let mut state = from.clone();
+        apply_delta(&mut state, &delta(&from, &to)).unwrap();
+        assert_eq!(state, to);
+    }
+
+    #[test]
+    fn delta_between_different_lengths() {
+        let mut state = vec![1, 2, 3];
+        apply_delta(&mut state, &delta(&[1, 2, 3], &[1, 2, 3, 4, 5])).unwrap();
+        assert_eq!(state, [1, 2, 3, 4, 5]);
+
+        apply_delta(&mut state, &delta(&[1, 2, 3, 4, 5], &[7])).unwrap();
+        assert_eq!(state, [7]);
+    }
+
+    #[test]
+    fn delta_of_identical_states_is_tiny() {
+        let state = vec![0xAB; 4096];
+        assert_eq!(delta(&state, &state), vec![0x80, 0x20]);
+    }
+
+    #[test]
+    fn varint_round_trip() {
+        for value in [0, 1, 0x7F, 0x80, 0x3FFF, 0x10000] {
+            let mut encoded = Vec::new();
+            write_varint(&mut encoded, value);
+            let mut data = &encoded[..];
+            assert_eq!(read_varint(&mut data), Some(value));
+            assert!(data.is_empty());
+        }
+    }
+
+    #[test]
+    fn malformed_deltas_are_rejected() {
+        // The length, then a single pair
+        let valid = delta(&[1, 2, 3], &[1, 5, 3, 4]);
+        assert_eq!(valid, [4, 1, 3, 7, 0, 4]);
+        for length in (0..valid.len()).filter(|length| *length != 1) {
+            let mut state = vec![1, 2, 3];
+            assert_eq!(apply_delta(&mut state, &valid[..length]), None);
+        }
+
+        // Runs past the end of both states
+        let mut state = vec![1, 2, 3];
+        assert_eq!(apply_delta(&mut state, &[3, 2, 2, 0xFF, 0xFF]), None);
+
+        let mut data = &[0xFF; 16][..];
+        assert_eq!(read_varint(&mut data), None);
+    }
+
+    #[test]
+    fn rewind_frames() {
+        let mut buffer = RewindBuffer::new(10);
+        for frame in 0..5u8 {
+            buffer.record(|writer| writer.bytes(&[frame, 0, frame * 2]));
+        }
+
+        assert_eq!(buffer.frames(), 4);
+        assert_eq!(buffer.rewind(2), Ok(Some((&[2, 0, 4][..], 2))));
+        assert_eq!(buffer.rewind(10), Ok(Some((&[0, 0, 0][..], 2))));
+        assert_eq!(buffer.frames(), 0);
+    }
+
+    #[test]
+    fn oldest_frames_are_dropped() {
+        let mut buffer = RewindBuffer::new(2);
+        for frame in 0..5u8 {
+            buffer.record(|writer| writer.u8(frame));
+        }
+
+        assert_eq!(buffer.rewind(5), Ok(Some((&[2][..], 2))));
+    }
+
+    #[test]
+    fn rewind_empty_buffer() {
+        let mut buffer = RewindBuffer::new(2);
+        assert_eq!(buffer.rewind(1), Ok(None));
+    }
+}
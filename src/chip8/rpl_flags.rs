@@ -0,0 +1,115 @@
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
+
+/// Flags available on any platform, SUPER-CHIP only having the first 8
+const FLAG_COUNT: usize = 16;
+
+/// The HP48 RPL user flags, used by SUPER-CHIP programs to keep data such as high scores around
+///
+/// When backed by a file, flags are read from it on creation. Saving only updates them in memory,
+/// `flush` writes them back once they changed, which also happens when they are dropped.
+#[derive(Default)]
+pub struct RplFlags {
+    flags: [u8; FLAG_COUNT],
+    path: Option<PathBuf>,
+    /// Whether the flags changed since they were last written to the file
+    dirty: bool,
+}
+
+impl RplFlags {
+    pub fn persistent(path: impl Into<PathBuf>) -> RplFlags {
+        let path = path.into();
+        let mut flags = [0; FLAG_COUNT];
+
+        match fs::read(&path) {
+            Ok(contents) => {
+                let length = contents.len().min(FLAG_COUNT);
+                flags[..length].copy_from_slice(&contents[..length]);
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => {}
+            Err(error) => warn!(
+                "Unable to read RPL flags from {}: {}",
+                path.display(),
+                error
+            ),
+        }
+
+        RplFlags {
+            flags,
+            path: Some(path),
+            dirty: false,
+        }
+    }
+
+    /// Stores the given values in the flags, starting from the first one
+    pub fn save(&mut self, values: &[u8]) {
+        if self.flags[..values.len()] != *values {
+            self.flags[..values.len()].copy_from_slice(values);
+            self.dirty = true;
+        }
+    }
+
+    /// Writes the flags to their file if they changed since the last time
+    pub fn flush(&mut self) -> io::Result<()> {
+        if let (Some(path), true) = (&self.path, self.dirty) {
+            fs::write(path, self.flags)?;
+        }
+
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Reads the first `count` flags
+    pub fn load(&self, count: usize) -> &[u8] {
+        &self.flags[..count]
+    }
+}
+
+impl Drop for RplFlags {
+    fn drop(&mut self) {
+        if let Err(error) = self.flush() {
+            if let Some(path) = &self.path {
+                error!("Unable to write RPL flags to {}: {}", path.display(), error);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load() {
+        let mut flags = RplFlags::default();
+        flags.save(&[1, 2, 3]);
+        assert_eq!(flags.load(4), &[1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn flags_persist_between_instances() {
+        let path = std::env::temp_dir().join(format!("chip8-rpl-{}.rpl", std::process::id()));
+
+        let mut flags = RplFlags::persistent(&path);
+        flags.save(&[4, 5]);
+        assert_eq!(RplFlags::persistent(&path).load(3), &[0, 0, 0]);
+
+        flags.flush().unwrap();
+        assert_eq!(RplFlags::persistent(&path).load(3), &[4, 5, 0]);
+
+        // Unchanged flags are not written again
+        fs::remove_file(&path).unwrap();
+        flags.save(&[4]);
+        flags.flush().unwrap();
+        assert!(!path.exists());
+
+        flags.save(&[6]);
+        drop(flags);
+        assert_eq!(RplFlags::persistent(&path).load(3), &[6, 5, 0]);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,264 @@
+//! Binary format of save states
+//!
+//! A state starts with the `MAGIC` bytes, the format `VERSION` and the FNV-1a hash of the ROM that
+//! was loaded, followed by every part of the machine in a fixed order. Numbers are little endian.
+
+use std::convert::TryInto;
+
+use super::{Chip8Error, ExecutionState, WatchpointHit};
+
+pub const MAGIC: &[u8; 4] = b"CH8S";
+
+/// Bumped whenever the layout changes, states from other versions are rejected
+pub const VERSION: u16 = 1;
+
+/// Part of the machine that can be written to and read back from a save state
+pub trait Snapshot: Sized {
+    fn save(&self, writer: &mut StateWriter);
+
+    fn restore(reader: &mut StateReader) -> Result<Self, Chip8Error>;
+}
+
+#[derive(Default)]
+pub struct StateWriter(Vec<u8>);
+
+impl StateWriter {
+    /// Writes into the given buffer after clearing it, to reuse its allocation
+    pub fn reusing(mut buffer: Vec<u8>) -> StateWriter {
+        buffer.clear();
+        StateWriter(buffer)
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn u128(&mut self, value: u128) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+/// Reads values in the order they were written, failing on truncated or malformed data
+pub struct StateReader<'a>(&'a [u8]);
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8]) -> StateReader<'a> {
+        StateReader(data)
+    }
+
+    pub fn bytes(&mut self, length: usize) -> Result<&'a [u8], Chip8Error> {
+        if self.0.len() < length {
+            return Err(Chip8Error::InvalidSaveState);
+        }
+
+        let (bytes, rest) = self.0.split_at(length);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    pub fn array<const N: usize>(&mut self) -> Result<[u8; N], Chip8Error> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+
+    pub fn u8(&mut self) -> Result<u8, Chip8Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn bool(&mut self) -> Result<bool, Chip8Error> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(Chip8Error::InvalidSaveState),
+        }
+    }
+
+    pub fn u16(&mut self) -> Result<u16, Chip8Error> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, Chip8Error> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    pub fn u128(&mut self) -> Result<u128, Chip8Error> {
+        Ok(u128::from_le_bytes(self.array()?))
+    }
+
+    /// Checks the whole state was read
+    pub fn finish(self) -> Result<(), Chip8Error> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(Chip8Error::InvalidSaveState)
+        }
+    }
+}
+
+/// 64 bit FNV-1a hash, used to tell which ROM a state was taken with
+pub fn fnv1a(data: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    data.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(PRIME)
+    })
+}
+
+impl Snapshot for ExecutionState {
+    fn save(&self, writer: &mut StateWriter) {
+        match self {
+            ExecutionState::Running => writer.u8(0),
+            ExecutionState::WaitingForKey => writer.u8(1),
+            ExecutionState::Halted => writer.u8(2),
+            ExecutionState::Faulted(error) => {
+                writer.u8(3);
+                error.save(writer);
+            }
+            ExecutionState::Watchpoint { address, hit } => {
+                writer.u8(4);
+                writer.u16(*address);
+                hit.save(writer);
+            }
+        }
+    }
+
+    fn restore(reader: &mut StateReader) -> Result<Self, Chip8Error> {
+        match reader.u8()? {
+            0 => Ok(ExecutionState::Running),
+            1 => Ok(ExecutionState::WaitingForKey),
+            2 => Ok(ExecutionState::Halted),
+            3 => Ok(ExecutionState::Faulted(Chip8Error::restore(reader)?)),
+            4 => Ok(ExecutionState::Watchpoint {
+                address: reader.u16()?,
+                hit: WatchpointHit::restore(reader)?,
+            }),
+            _ => Err(Chip8Error::InvalidSaveState),
+        }
+    }
+}
+
+/// Errors are stored as a tag followed by two numbers, unused ones being 0
+impl Snapshot for Chip8Error {
+    fn save(&self, writer: &mut StateWriter) {
+        let (tag, first, second) = match *self {
+            Chip8Error::InvalidOpCode { address, op_code } => (0, address as u64, op_code as u64),
+            Chip8Error::ProgramCounterOutOfRange { address } => (1, address as u64, 0),
+            Chip8Error::StackOverflow { address } => (2, address as u64, 0),
+            Chip8Error::StackUnderflow { address } => (3, address as u64, 0),
+            Chip8Error::InvalidFontDigit { digit } => (4, digit as u64, 0),
+            Chip8Error::MemoryOutOfBounds { address } => (5, address as u64, 0),
+            Chip8Error::RomTooLarge { size, capacity } => (6, size as u64, capacity as u64),
+            Chip8Error::InvalidSaveState => (7, 0, 0),
+            Chip8Error::UnsupportedSaveStateVersion { version } => (8, version as u64, 0),
+            Chip8Error::SaveStateRomMismatch => (9, 0, 0),
+        };
+
+        writer.u8(tag);
+        writer.u64(first);
+        writer.u64(second);
+    }
+
+    fn restore(reader: &mut StateReader) -> Result<Self, Chip8Error> {
+        let tag = reader.u8()?;
+        let first = reader.u64()?;
+        let second = reader.u64()?;
+
+        Ok(match tag {
+            0 => Chip8Error::InvalidOpCode {
+                address: first as u16,
+                op_code: second as u16,
+            },
+            1 => Chip8Error::ProgramCounterOutOfRange {
+                address: first as u16,
+            },
+            2 => Chip8Error::StackOverflow {
+                address: first as u16,
+            },
+            3 => Chip8Error::StackUnderflow {
+                address: first as u16,
+            },
+            4 => Chip8Error::InvalidFontDigit { digit: first as u8 },
+            5 => Chip8Error::MemoryOutOfBounds {
+                address: first as u16,
+            },
+            6 => Chip8Error::RomTooLarge {
+                size: first as usize,
+                capacity: second as usize,
+            },
+            7 => Chip8Error::InvalidSaveState,
+            8 => Chip8Error::UnsupportedSaveStateVersion {
+                version: first as u16,
+            },
+            9 => Chip8Error::SaveStateRomMismatch,
+            _ => return Err(Chip8Error::InvalidSaveState),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_known_values() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn read_back_written_values() {
+        let mut writer = StateWriter::default();
+        writer.u8(1);
+        writer.bool(true);
+        writer.u16(0x1234);
+        writer.u128(u128::MAX - 1);
+        let bytes = writer.into_bytes();
+
+        let mut reader = StateReader::new(&bytes);
+        assert_eq!(reader.u8(), Ok(1));
+        assert_eq!(reader.bool(), Ok(true));
+        assert_eq!(reader.u16(), Ok(0x1234));
+        assert_eq!(reader.u128(), Ok(u128::MAX - 1));
+        assert_eq!(reader.finish(), Ok(()));
+    }
+
+    #[test]
+    fn read_truncated_data() {
+        let mut reader = StateReader::new(&[1]);
+        assert_eq!(reader.u16(), Err(Chip8Error::InvalidSaveState));
+    }
+
+    #[test]
+    fn faulted_state_round_trip() {
+        let state = ExecutionState::Faulted(Chip8Error::InvalidOpCode {
+            address: 0x204,
+            op_code: 0xFFFF,
+        });
+        let mut writer = StateWriter::default();
+        state.save(&mut writer);
+        let bytes = writer.into_bytes();
+
+        let mut reader = StateReader::new(&bytes);
+        assert_eq!(ExecutionState::restore(&mut reader), Ok(state));
+    }
+}
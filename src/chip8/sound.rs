@@ -0,0 +1,146 @@
+use super::{Chip8Error, Snapshot, StateReader, StateWriter};
+
+/// Length of an XO-CHIP audio pattern in bytes, played back one bit at a time
+pub const PATTERN_LENGTH: usize = 16;
+
+/// Pitch register value at which the pattern is played at `BASE_PLAYBACK_RATE`
+const DEFAULT_PITCH: u8 = 64;
+
+/// Pattern bits played per second with the default pitch
+const BASE_PLAYBACK_RATE: f64 = 4000.0;
+
+/// Amplitude of the generated samples
+const VOLUME: f32 = 0.25;
+
+/// XO-CHIP audio state: a 1-bit pattern looped for as long as the sound timer is non-zero, at a
+/// rate set by the pitch register. Programs that never load a pattern get a plain square wave.
+pub struct Sound {
+    pattern: [u8; PATTERN_LENGTH],
+    pitch: u8,
+    active: bool,
+    /// Position in the pattern, in bits
+    phase: f64,
+}
+
+impl Sound {
+    pub fn set_pattern(&mut self, pattern: &[u8; PATTERN_LENGTH]) {
+        self.pattern = *pattern;
+    }
+
+    pub fn set_pitch(&mut self, pitch: u8) {
+        self.pitch = pitch;
+    }
+
+    /// Whether the buzzer should be sounding, which is the case while the sound timer is non-zero
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+    }
+
+    /// Takes the pattern, pitch and active state of another sound, keeping the playback position
+    pub fn sync(&mut self, other: &Sound) {
+        self.pattern = other.pattern;
+        self.pitch = other.pitch;
+        self.active = other.active;
+    }
+
+    /// Pattern bits played per second, doubling every 48 steps of the pitch register
+    pub fn playback_rate(&self) -> f64 {
+        BASE_PLAYBACK_RATE * 2f64.powf((self.pitch as f64 - DEFAULT_PITCH as f64) / 48.0)
+    }
+
+    /// Fills `samples` with mono PCM at the given sample rate, continuing where the previous call
+    /// left off. Produces silence while the sound timer is zero.
+    pub fn generate(&mut self, sample_rate: u32, samples: &mut [f32]) {
+        if !self.active {
+            samples.fill(0.0);
+            return;
+        }
+
+        let step = self.playback_rate() / sample_rate as f64;
+        let pattern_bits = (PATTERN_LENGTH * 8) as f64;
+
+        for sample in samples {
+            let bit = self.phase as usize;
+            let set = self.pattern[bit / 8] >> (7 - bit % 8) & 1 == 1;
+            *sample = if set { VOLUME } else { -VOLUME };
+            self.phase = (self.phase + step) % pattern_bits;
+        }
+    }
+}
+
+impl Default for Sound {
+    fn default() -> Self {
+        Sound {
+            pattern: [0xF0; PATTERN_LENGTH],
+            pitch: DEFAULT_PITCH,
+            active: false,
+            phase: 0.0,
+        }
+    }
+}
+
+/// Only the pattern and pitch are stored, whether it is active follows from the sound timer. The
+/// playback position belongs to the host's copy, which `sync` leaves alone when a state is loaded.
+impl Snapshot for Sound {
+    fn save(&self, writer: &mut StateWriter) {
+        writer.bytes(&self.pattern);
+        writer.u8(self.pitch);
+    }
+
+    fn restore(reader: &mut StateReader) -> Result<Self, Chip8Error> {
+        Ok(Sound {
+            pattern: reader.array()?,
+            pitch: reader.u8()?,
+            ..Sound::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn silent_while_inactive() {
+        let mut sound = Sound::default();
+        let mut samples = [1.0; 8];
+        sound.generate(48000, &mut samples);
+        assert!(samples.iter().all(|sample| *sample == 0.0));
+    }
+
+    #[test]
+    fn plays_pattern_bits() {
+        let mut sound = Sound::default();
+        sound.set_pattern(&[0b10100000; PATTERN_LENGTH]);
+        sound.set_active(true);
+        let mut samples = [0.0; 4];
+        // One bit per sample at the default pitch
+        sound.generate(4000, &mut samples);
+        assert_eq!(samples, [VOLUME, -VOLUME, VOLUME, -VOLUME]);
+    }
+
+    #[test]
+    fn continues_from_previous_call() {
+        let mut sound = Sound::default();
+        sound.set_pattern(&[0b11000000; PATTERN_LENGTH]);
+        sound.set_active(true);
+        let mut samples = [0.0; 2];
+        sound.generate(4000, &mut samples);
+        sound.generate(4000, &mut samples);
+        assert_eq!(samples, [-VOLUME, -VOLUME]);
+    }
+
+    #[test]
+    fn pitch_doubles_rate_every_48_steps() {
+        let mut sound = Sound::default();
+        assert_eq!(sound.playback_rate(), 4000.0);
+        sound.set_pitch(112);
+        assert_eq!(sound.playback_rate(), 8000.0);
+        sound.set_pitch(16);
+        assert_eq!(sound.playback_rate(), 2000.0);
+    }
+}
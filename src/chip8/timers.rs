@@ -0,0 +1,76 @@
+use super::{Chip8Error, Snapshot, StateReader, StateWriter};
+
+#[derive(Default)]
+pub struct Timers {
+    delay: u8,
+    sound: u8,
+}
+
+impl Timers {
+    pub fn delay(&self) -> u8 {
+        self.delay
+    }
+
+    pub fn sound(&self) -> u8 {
+        self.sound
+    }
+
+    pub fn set_delay(&mut self, value: u8) {
+        self.delay = value;
+    }
+
+    pub fn set_sound(&mut self, value: u8) {
+        self.sound = value;
+    }
+
+    /// Whether the buzzer should be sounding, which is the case while the sound timer is non-zero
+    pub fn is_sound_active(&self) -> bool {
+        self.sound > 0
+    }
+
+    /// Decrements both timers by one, stopping at zero
+    pub fn tick(&mut self) {
+        self.delay = self.delay.saturating_sub(1);
+        self.sound = self.sound.saturating_sub(1);
+    }
+}
+
+impl Snapshot for Timers {
+    fn save(&self, writer: &mut StateWriter) {
+        writer.u8(self.delay);
+        writer.u8(self.sound);
+    }
+
+    fn restore(reader: &mut StateReader) -> Result<Self, Chip8Error> {
+        Ok(Timers {
+            delay: reader.u8()?,
+            sound: reader.u8()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tick_decrements_both_timers() {
+        let mut timers = Timers::default();
+        timers.set_delay(2);
+        timers.set_sound(3);
+        timers.tick();
+        assert_eq!(timers.delay(), 1);
+        assert_eq!(timers.sound(), 2);
+    }
+
+    #[test]
+    fn tick_stops_at_zero() {
+        let mut timers = Timers::default();
+        timers.set_sound(1);
+        timers.tick();
+        timers.tick();
+        assert_eq!(timers.delay(), 0);
+        assert_eq!(timers.sound(), 0);
+        assert!(!timers.is_sound_active());
+    }
+}
@@ -0,0 +1,184 @@
+use std::{convert::TryFrom, fmt, ops::RangeInclusive};
+
+use super::{Chip8Error, Snapshot, StateReader, StateWriter, VRegister};
+
+/// Kind of memory access a watchpoint stops on
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MemoryAccess {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl MemoryAccess {
+    fn includes_reads(self) -> bool {
+        self != MemoryAccess::Write
+    }
+
+    fn includes_writes(self) -> bool {
+        self != MemoryAccess::Read
+    }
+}
+
+/// Condition that stops execution after the instruction meeting it
+#[derive(Debug, PartialEq, Clone)]
+pub enum Watchpoint {
+    /// An instruction reads or writes an address in the range
+    Memory {
+        range: RangeInclusive<u16>,
+        access: MemoryAccess,
+    },
+
+    /// The register is set to a different value
+    RegisterChange(VRegister),
+
+    /// The register is set to the given value, from any other one
+    RegisterValue(VRegister, u8),
+}
+
+impl Watchpoint {
+    /// Whether reading the address triggers this watchpoint
+    pub fn is_memory_read(&self, address: u16) -> bool {
+        matches!(self, Watchpoint::Memory { range, access }
+            if access.includes_reads() && range.contains(&address))
+    }
+
+    /// Whether writing the address triggers this watchpoint
+    pub fn is_memory_write(&self, address: u16) -> bool {
+        matches!(self, Watchpoint::Memory { range, access }
+            if access.includes_writes() && range.contains(&address))
+    }
+
+    /// Whether setting the register from `old` to `new` triggers this watchpoint
+    pub fn is_register_write(&self, register: VRegister, old: u8, new: u8) -> bool {
+        match *self {
+            Watchpoint::RegisterChange(watched) => watched == register && old != new,
+            Watchpoint::RegisterValue(watched, value) => {
+                watched == register && old != value && new == value
+            }
+            Watchpoint::Memory { .. } => false,
+        }
+    }
+}
+
+/// Access that triggered a watchpoint
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum WatchpointHit {
+    MemoryRead {
+        address: u16,
+        value: u8,
+    },
+    MemoryWrite {
+        address: u16,
+        old: u8,
+        new: u8,
+    },
+    RegisterWrite {
+        register: VRegister,
+        old: u8,
+        new: u8,
+    },
+}
+
+impl fmt::Display for WatchpointHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchpointHit::MemoryRead { address, value } => {
+                write!(f, "Read {:02x} from {:#05x}", value, address)
+            }
+            WatchpointHit::MemoryWrite { address, old, new } => {
+                write!(f, "Wrote {:02x} to {:#05x}, was {:02x}", new, address, old)
+            }
+            WatchpointHit::RegisterWrite { register, old, new } => {
+                write!(f, "Set {:?} to {:02x}, was {:02x}", register, new, old)
+            }
+        }
+    }
+}
+
+/// Hits are stored as a tag, an address or register and two values, unused ones being 0
+impl Snapshot for WatchpointHit {
+    fn save(&self, writer: &mut StateWriter) {
+        let (tag, location, first, second) = match *self {
+            WatchpointHit::MemoryRead { address, value } => (0, address, value, 0),
+            WatchpointHit::MemoryWrite { address, old, new } => (1, address, old, new),
+            WatchpointHit::RegisterWrite { register, old, new } => {
+                (2, usize::from(register) as u16, old, new)
+            }
+        };
+
+        writer.u8(tag);
+        writer.u16(location);
+        writer.u8(first);
+        writer.u8(second);
+    }
+
+    fn restore(reader: &mut StateReader) -> Result<Self, Chip8Error> {
+        let tag = reader.u8()?;
+        let location = reader.u16()?;
+        let first = reader.u8()?;
+        let second = reader.u8()?;
+
+        Ok(match tag {
+            0 => WatchpointHit::MemoryRead {
+                address: location,
+                value: first,
+            },
+            1 => WatchpointHit::MemoryWrite {
+                address: location,
+                old: first,
+                new: second,
+            },
+            2 => WatchpointHit::RegisterWrite {
+                register: u8::try_from(location)
+                    .ok()
+                    .and_then(|register| VRegister::try_from(register).ok())
+                    .ok_or(Chip8Error::InvalidSaveState)?,
+                old: first,
+                new: second,
+            },
+            _ => return Err(Chip8Error::InvalidSaveState),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_access_kinds() {
+        let watchpoint = Watchpoint::Memory {
+            range: 0x300..=0x30F,
+            access: MemoryAccess::Write,
+        };
+        assert!(watchpoint.is_memory_write(0x300));
+        assert!(watchpoint.is_memory_write(0x30F));
+        assert!(!watchpoint.is_memory_write(0x310));
+        assert!(!watchpoint.is_memory_read(0x300));
+    }
+
+    #[test]
+    fn register_value() {
+        let watchpoint = Watchpoint::RegisterValue(VRegister::V3, 5);
+        assert!(watchpoint.is_register_write(VRegister::V3, 4, 5));
+        assert!(!watchpoint.is_register_write(VRegister::V3, 5, 5));
+        assert!(!watchpoint.is_register_write(VRegister::V3, 5, 6));
+        assert!(!watchpoint.is_register_write(VRegister::V4, 4, 5));
+    }
+
+    #[test]
+    fn hit_round_trip() {
+        let hit = WatchpointHit::RegisterWrite {
+            register: VRegister::VF,
+            old: 0,
+            new: 1,
+        };
+        let mut writer = StateWriter::default();
+        hit.save(&mut writer);
+        let bytes = writer.into_bytes();
+
+        let mut reader = StateReader::new(&bytes);
+        assert_eq!(WatchpointHit::restore(&mut reader), Ok(hit));
+    }
+}
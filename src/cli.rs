@@ -0,0 +1,430 @@
+use std::{convert::TryFrom, fs, path::PathBuf, str::FromStr};
+
+use chip8::{
+    InputKey, MemoryAccess, Platform, Quirks, VRegister, Watchpoint,
+    DEFAULT_INSTRUCTIONS_PER_FRAME, DEFAULT_LOAD_ADDRESS,
+};
+use log::LevelFilter;
+
+pub const USAGE: &str = "\
+Usage: chip8 [OPTIONS] <ROM>
+
+Options:
+  --platform <NAME>     Machine and quirks to run the ROM with: vip, schip, xo-chip, or octo for
+                        Octo's CHIP-8 defaults [default: vip]
+  --load-address <HEX>  Address the ROM is loaded and started at, 600 for ETI-660 ROMs
+                        [default: 200]
+  --ipf <COUNT>         Instructions executed per 60 Hz frame [default: 11]
+  --seed <NUMBER>       Seed for the random number generator, for reproducible runs
+  --keymap <FILE>       File mapping keyboard keys to keypad keys, one `<key> <hex digit>` per line
+  --log-level <LEVEL>   off, error, warn, info, debug or trace [default: RUST_LOG or error]
+  --headless            Run without a terminal display, printing the screen at the end
+  --frames <COUNT>      Number of frames to run in headless mode
+  --debug               Start paused in the debugger, with the registers shown next to the screen
+  --break <HEX>         Breakpoint address for the debugger, can be repeated
+  --watch <SPEC>        Stop on accesses to memory (300, 300-30f, 300:r, 300:w) or when a register
+                        changes (v3) or takes a value (v3=05), with --debug or --headless
+  -h, --help            Print this message
+
+Terminals only report key presses and their auto-repeats, so a key counts as released once it
+stops repeating for half a second. Quick taps are held that long.";
+
+/// Keyboard key for each key of the keypad
+pub const DEFAULT_KEY_MAP: [(char, InputKey); 16] = [
+    ('0', InputKey::Key0),
+    ('1', InputKey::Key1),
+    ('2', InputKey::Key2),
+    ('3', InputKey::Key3),
+    ('4', InputKey::Key4),
+    ('5', InputKey::Key5),
+    ('6', InputKey::Key6),
+    ('7', InputKey::Key7),
+    ('8', InputKey::Key8),
+    ('9', InputKey::Key9),
+    ('a', InputKey::KeyA),
+    ('b', InputKey::KeyB),
+    ('c', InputKey::KeyC),
+    ('d', InputKey::KeyD),
+    ('e', InputKey::KeyE),
+    ('f', InputKey::KeyF),
+];
+
+/// What to run and how, as given on the command line
+#[derive(Debug)]
+pub struct Options {
+    pub rom: PathBuf,
+    pub platform: Platform,
+    pub quirks: Quirks,
+    pub load_address: u16,
+    pub instructions_per_frame: usize,
+    pub seed: Option<u64>,
+    pub key_map: Vec<(char, InputKey)>,
+    pub log_level: Option<LevelFilter>,
+    /// Number of frames to run without a display, `None` when running in the terminal
+    pub headless_frames: Option<u64>,
+    pub debug: bool,
+    pub breakpoints: Vec<u16>,
+    pub watchpoints: Vec<Watchpoint>,
+    pub help: bool,
+}
+
+impl Options {
+    /// Parses the arguments following the program name
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+        let mut args = args.into_iter();
+        let mut rom = None;
+        let mut platform = Platform::Chip8;
+        let mut quirks = Quirks::COSMAC_VIP;
+        let mut load_address = DEFAULT_LOAD_ADDRESS;
+        let mut instructions_per_frame = DEFAULT_INSTRUCTIONS_PER_FRAME;
+        let mut seed = None;
+        let mut key_map = DEFAULT_KEY_MAP.to_vec();
+        let mut log_level = None;
+        let mut headless = false;
+        let mut frames = None;
+        let mut debug = false;
+        let mut breakpoints = Vec::new();
+        let mut watchpoints = Vec::new();
+        let mut help = false;
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("Missing value for {}", arg))
+            };
+
+            match arg.as_str() {
+                "--platform" => (platform, quirks) = parse_platform(&value()?)?,
+                "--load-address" => load_address = parse_address("--load-address", &value()?)?,
+                "--ipf" => instructions_per_frame = parse_number("--ipf", &value()?)?,
+                "--seed" => seed = Some(parse_number("--seed", &value()?)?),
+                "--keymap" => key_map = read_key_map(&value()?)?,
+                "--log-level" => log_level = Some(parse_number("--log-level", &value()?)?),
+                "--headless" => headless = true,
+                "--frames" => frames = Some(parse_number("--frames", &value()?)?),
+                "--debug" => debug = true,
+                "--break" => breakpoints.push(parse_address("--break", &value()?)?),
+                "--watch" => watchpoints.push(parse_watchpoint(&value()?)?),
+                "-h" | "--help" => help = true,
+                _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+                _ if rom.is_some() => return Err(format!("Unexpected argument {}", arg)),
+                _ => rom = Some(PathBuf::from(arg)),
+            }
+        }
+
+        let headless_frames = match (headless, frames) {
+            (true, Some(frames)) => Some(frames),
+            (true, None) => return Err("--headless requires --frames".to_string()),
+            (false, Some(_)) => return Err("--frames only applies with --headless".to_string()),
+            (false, None) => None,
+        };
+
+        if instructions_per_frame == 0 {
+            return Err("--ipf must be at least 1".to_string());
+        }
+
+        if debug && headless {
+            return Err("--debug cannot be used with --headless".to_string());
+        }
+
+        if !watchpoints.is_empty() && !debug && !headless {
+            return Err("--watch requires --debug or --headless".to_string());
+        }
+
+        let rom = match rom {
+            Some(rom) => rom,
+            None if help => PathBuf::new(),
+            None => return Err("Missing ROM path".to_string()),
+        };
+
+        Ok(Options {
+            rom,
+            platform,
+            quirks,
+            load_address,
+            instructions_per_frame,
+            seed,
+            key_map,
+            log_level,
+            headless_frames,
+            debug,
+            breakpoints,
+            watchpoints,
+            help,
+        })
+    }
+
+    pub fn read_rom(&self) -> Result<Vec<u8>, String> {
+        fs::read(&self.rom)
+            .map_err(|error| format!("Unable to read {}: {}", self.rom.display(), error))
+    }
+}
+
+/// Octo runs CHIP-8 programs with the XO-CHIP quirks, but in 4 KiB of memory
+fn parse_platform(name: &str) -> Result<(Platform, Quirks), String> {
+    match name {
+        "vip" => Ok((Platform::Chip8, Quirks::COSMAC_VIP)),
+        "schip" => Ok((Platform::Chip8, Quirks::SCHIP)),
+        "xo-chip" => Ok((Platform::XoChip, Quirks::XO_CHIP)),
+        "octo" => Ok((Platform::Chip8, Quirks::OCTO)),
+        _ => Err(format!("Unknown platform {}", name)),
+    }
+}
+
+/// Parses a hexadecimal address, with or without a `0x` prefix
+fn parse_address(option: &str, value: &str) -> Result<u16, String> {
+    let digits = value.trim_start_matches("0x");
+    u16::from_str_radix(digits, 16).map_err(|_| format!("Invalid value for {}: {}", option, value))
+}
+
+/// Parses `v<register>` or `v<register>=<hex value>` for registers, and
+/// `<hex address>[-<hex address>][:r|:w]` for memory, which is watched for both reads and writes
+/// when neither is given
+fn parse_watchpoint(value: &str) -> Result<Watchpoint, String> {
+    let invalid = || format!("Invalid value for --watch: {}", value);
+
+    if let Some(register) = value.strip_prefix('v') {
+        let (register, register_value) = match register.split_once('=') {
+            Some((register, register_value)) => (register, Some(register_value)),
+            None => (register, None),
+        };
+
+        let register = u8::from_str_radix(register, 16)
+            .ok()
+            .and_then(|register| VRegister::try_from(register).ok())
+            .ok_or_else(invalid)?;
+
+        return match register_value {
+            Some(register_value) => u8::from_str_radix(register_value, 16)
+                .map(|register_value| Watchpoint::RegisterValue(register, register_value))
+                .map_err(|_| invalid()),
+            None => Ok(Watchpoint::RegisterChange(register)),
+        };
+    }
+
+    let (range, access) = match value.split_once(':') {
+        Some((range, "r")) => (range, MemoryAccess::Read),
+        Some((range, "w")) => (range, MemoryAccess::Write),
+        Some(_) => return Err(invalid()),
+        None => (value, MemoryAccess::ReadWrite),
+    };
+
+    let (start, end) = range.split_once('-').unwrap_or((range, range));
+    let start = parse_address("--watch", start)?;
+    let end = parse_address("--watch", end)?;
+
+    if start > end {
+        return Err(invalid());
+    }
+
+    Ok(Watchpoint::Memory {
+        range: start..=end,
+        access,
+    })
+}
+
+fn parse_number<T: FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", option, value))
+}
+
+fn read_key_map(path: &str) -> Result<Vec<(char, InputKey)>, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|error| format!("Unable to read {}: {}", path, error))?;
+
+    parse_key_map(&contents).map_err(|error| format!("{}: {}", path, error))
+}
+
+/// Parses lines of `<keyboard key> <keypad key>`, such as `x 0`. Empty lines and lines starting
+/// with `#` are ignored.
+fn parse_key_map(contents: &str) -> Result<Vec<(char, InputKey)>, String> {
+    let mut key_map = Vec::new();
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid = || format!("Invalid key mapping on line {}: {}", number + 1, line);
+        let mut parts = line.split_whitespace();
+
+        let mut keyboard_key = parts.next().ok_or_else(invalid)?.chars();
+        let character = keyboard_key.next().ok_or_else(invalid)?;
+        let keypad_key = parts
+            .next()
+            .and_then(|key| u8::from_str_radix(key, 16).ok())
+            .and_then(|key| InputKey::try_from(key).ok())
+            .ok_or_else(invalid)?;
+
+        if keyboard_key.next().is_some() || parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        key_map.push((character, keypad_key));
+    }
+
+    Ok(key_map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse_defaults() {
+        let options = parse(&["game.ch8"]).unwrap();
+        assert_eq!(options.rom, PathBuf::from("game.ch8"));
+        assert_eq!(options.platform, Platform::Chip8);
+        assert_eq!(options.quirks, Quirks::COSMAC_VIP);
+        assert_eq!(
+            options.instructions_per_frame,
+            DEFAULT_INSTRUCTIONS_PER_FRAME
+        );
+        assert_eq!(options.seed, None);
+        assert_eq!(options.key_map, DEFAULT_KEY_MAP.to_vec());
+        assert_eq!(options.headless_frames, None);
+        assert!(!options.debug);
+        assert!(options.breakpoints.is_empty());
+    }
+
+    #[test]
+    fn parse_debug_options() {
+        let options =
+            parse(&["--debug", "--break", "2a0", "--break", "0x300", "game.ch8"]).unwrap();
+        assert!(options.debug);
+        assert_eq!(options.breakpoints, vec![0x2A0, 0x300]);
+    }
+
+    #[test]
+    fn parse_all_options() {
+        let options = parse(&[
+            "--platform",
+            "schip",
+            "--ipf",
+            "30",
+            "--seed",
+            "42",
+            "--load-address",
+            "0x600",
+            "--log-level",
+            "debug",
+            "--headless",
+            "--frames",
+            "600",
+            "game.ch8",
+        ])
+        .unwrap();
+        assert_eq!(options.quirks, Quirks::SCHIP);
+        assert_eq!(options.instructions_per_frame, 30);
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(options.load_address, 0x600);
+        assert_eq!(options.log_level, Some(LevelFilter::Debug));
+        assert_eq!(options.headless_frames, Some(600));
+    }
+
+    #[test]
+    fn parse_platforms() {
+        let options = parse(&["--platform", "xo-chip", "game.ch8"]).unwrap();
+        assert_eq!(options.platform, Platform::XoChip);
+        assert_eq!(options.quirks, Quirks::XO_CHIP);
+
+        let options = parse(&["--platform", "octo", "game.ch8"]).unwrap();
+        assert_eq!(options.platform, Platform::Chip8);
+        assert_eq!(options.quirks, Quirks::OCTO);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["a.ch8", "b.ch8"]).is_err());
+        assert!(parse(&["--platform", "cosmac", "game.ch8"]).is_err());
+        assert!(parse(&["--ipf", "fast", "game.ch8"]).is_err());
+        assert!(parse(&["--ipf", "0", "game.ch8"]).is_err());
+        assert!(parse(&["--load-address", "10000", "game.ch8"]).is_err());
+        assert!(parse(&["game.ch8", "--seed"]).is_err());
+        assert!(parse(&["--headless", "game.ch8"]).is_err());
+        assert!(parse(&["--frames", "10", "game.ch8"]).is_err());
+        assert!(parse(&["--verbose", "game.ch8"]).is_err());
+        assert!(parse(&["--break", "start", "game.ch8"]).is_err());
+        assert!(parse(&["--debug", "--headless", "--frames", "1", "game.ch8"]).is_err());
+    }
+
+    #[test]
+    fn parse_watchpoints() {
+        let options = parse(&[
+            "--headless",
+            "--frames",
+            "1",
+            "--watch",
+            "v3",
+            "--watch",
+            "vf=01",
+            "--watch",
+            "300-30f",
+            "--watch",
+            "0x400:w",
+            "game.ch8",
+        ])
+        .unwrap();
+        assert_eq!(
+            options.watchpoints,
+            vec![
+                Watchpoint::RegisterChange(VRegister::V3),
+                Watchpoint::RegisterValue(VRegister::VF, 1),
+                Watchpoint::Memory {
+                    range: 0x300..=0x30F,
+                    access: MemoryAccess::ReadWrite
+                },
+                Watchpoint::Memory {
+                    range: 0x400..=0x400,
+                    access: MemoryAccess::Write
+                },
+            ]
+        );
+
+        assert!(parse_watchpoint("v10").is_err());
+        assert!(parse_watchpoint("v3=100").is_err());
+        assert!(parse_watchpoint("30f-300").is_err());
+        assert!(parse_watchpoint("300:x").is_err());
+        assert!(parse(&["--watch", "v3", "game.ch8"]).is_err());
+    }
+
+    #[test]
+    fn parse_help_without_rom() {
+        assert!(parse(&["--help"]).unwrap().help);
+    }
+
+    #[test]
+    fn parse_key_map_file() {
+        let key_map = parse_key_map("# COSMAC VIP layout\nx 0\n\n1 1\nv f\n").unwrap();
+        assert_eq!(
+            key_map,
+            vec![
+                ('x', InputKey::Key0),
+                ('1', InputKey::Key1),
+                ('v', InputKey::KeyF)
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_key_map() {
+        assert!(parse_key_map("x").is_err());
+        assert!(parse_key_map("x 10").is_err());
+        assert!(parse_key_map("xy 1").is_err());
+        assert!(parse_key_map("x 1 2").is_err());
+    }
+
+    #[test]
+    fn read_missing_rom() {
+        let options = parse(&["does/not/exist.ch8"]).unwrap();
+        assert!(options.read_rom().is_err());
+    }
+}
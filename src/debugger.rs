@@ -0,0 +1,372 @@
+//! Debugger for the terminal frontend, to pause the program, step through it and stop at
+//! breakpoints while looking at the registers
+
+use std::{collections::BTreeSet, convert::TryFrom};
+
+use chip8::{Chip8, Chip8Error, ExecutionState, OpCode, VRegister};
+use console_engine::{ConsoleEngine, KeyCode};
+
+/// Columns taken by the panel drawn next to the screen
+pub const PANEL_WIDTH: u32 = 34;
+
+pub const HELP: &str = "F6: run/pause  F7: step  F8: step over  F2: breakpoint";
+
+const HEX_DIGITS: &str = "0123456789abcdef";
+
+pub struct Debugger {
+    paused: bool,
+    /// Set when execution continues from a breakpoint, so that it doesn't stop there again
+    resuming: bool,
+    breakpoints: BTreeSet<u16>,
+    /// Stack depth to get back to before pausing, while stepping over a subroutine call
+    step_over_depth: Option<usize>,
+    /// Address being typed to toggle a breakpoint
+    prompt: Option<String>,
+}
+
+impl Debugger {
+    /// Creates a debugger with the given breakpoints, paused before the first instruction
+    pub fn new(breakpoints: &[u16]) -> Debugger {
+        Debugger {
+            paused: true,
+            resuming: false,
+            breakpoints: breakpoints.iter().copied().collect(),
+            step_over_depth: None,
+            prompt: None,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Whether a breakpoint address is being typed, in which case keys are not meant for the keypad
+    pub fn is_prompting(&self) -> bool {
+        self.prompt.is_some()
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.step_over_depth = None;
+    }
+
+    /// Continues execution, including after a watchpoint stopped it
+    pub fn resume(&mut self, chip8: &mut Chip8) {
+        chip8.resume();
+        self.paused = false;
+        self.resuming = true;
+    }
+
+    /// Adds a breakpoint at the given address, or removes it if there already is one
+    pub fn toggle_breakpoint(&mut self, address: u16) {
+        if !self.breakpoints.remove(&address) {
+            self.breakpoints.insert(address);
+        }
+    }
+
+    /// Executes a single instruction and stays paused, going on from a watchpoint if one stopped
+    /// execution
+    pub fn step(&mut self, chip8: &mut Chip8) -> Result<(), Chip8Error> {
+        self.pause();
+        chip8.resume();
+        self.execute(chip8).map(|_| ())
+    }
+
+    /// Like `step`, but runs a subroutine call until it returns
+    pub fn step_over(&mut self, chip8: &mut Chip8) -> Result<(), Chip8Error> {
+        match next_instruction(chip8) {
+            Some((_, Some(OpCode::Call(_)))) => {
+                self.resume(chip8);
+                self.step_over_depth = Some(chip8.stack().len());
+                Ok(())
+            }
+            _ => self.step(chip8),
+        }
+    }
+
+    /// Runs the rest of the current frame unless paused, stopping at breakpoints, watchpoints, at
+    /// the end of a stepped over call and on faults
+    pub fn run_frame(&mut self, chip8: &mut Chip8) -> Result<(), Chip8Error> {
+        if self.paused {
+            return Ok(());
+        }
+
+        loop {
+            let at_breakpoint = self.breakpoints.contains(&chip8.program_counter());
+
+            if chip8.state().is_active() && !self.resuming && at_breakpoint {
+                self.pause();
+                return Ok(());
+            }
+
+            self.resuming = false;
+            let frame_ended = self.execute(chip8)?;
+
+            if self.is_paused() {
+                return Ok(());
+            }
+
+            if self
+                .step_over_depth
+                .is_some_and(|depth| chip8.stack().len() <= depth)
+            {
+                self.pause();
+                return Ok(());
+            }
+
+            if frame_ended {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Handles the debugger keys and the breakpoint prompt
+    pub fn handle_keys(
+        &mut self,
+        engine: &ConsoleEngine,
+        chip8: &mut Chip8,
+    ) -> Result<(), Chip8Error> {
+        if let Some(prompt) = &mut self.prompt {
+            for digit in HEX_DIGITS.chars() {
+                if engine.is_key_pressed(KeyCode::Char(digit)) && prompt.len() < 4 {
+                    prompt.push(digit);
+                }
+            }
+
+            if engine.is_key_pressed(KeyCode::Backspace) {
+                prompt.pop();
+            }
+
+            if engine.is_key_pressed(KeyCode::Esc) {
+                self.prompt = None;
+            } else if engine.is_key_pressed(KeyCode::Enter) {
+                // An empty address means the instruction about to be executed
+                let address = u16::from_str_radix(prompt, 16).unwrap_or(chip8.program_counter());
+                self.toggle_breakpoint(address);
+                self.prompt = None;
+            }
+
+            return Ok(());
+        }
+
+        if engine.is_key_pressed(KeyCode::F(2)) {
+            self.prompt = Some(String::new());
+        }
+
+        if engine.is_key_pressed(KeyCode::F(6)) {
+            if self.is_paused() {
+                self.resume(chip8);
+            } else {
+                self.pause();
+            }
+        }
+
+        if self.is_paused() && engine.is_key_pressed(KeyCode::F(7)) {
+            self.step(chip8)?;
+        }
+
+        if self.is_paused() && engine.is_key_pressed(KeyCode::F(8)) {
+            self.step_over(chip8)?;
+        }
+
+        Ok(())
+    }
+
+    /// Draws the state of the machine, with its top left corner at the given position
+    pub fn draw_panel(&self, engine: &mut ConsoleEngine, chip8: &Chip8, x: i32, y: i32) {
+        let mut lines = vec![
+            if self.is_paused() {
+                "Paused"
+            } else {
+                "Running"
+            }
+            .to_string(),
+            format!(
+                "PC {:#05x}  I {:#05x}",
+                chip8.program_counter(),
+                chip8.index()
+            ),
+        ];
+
+        let marker = if self.breakpoints.contains(&chip8.program_counter()) {
+            '*'
+        } else {
+            ' '
+        };
+        lines.push(match next_instruction(chip8) {
+            Some((word, Some(op_code))) => format!("{}{:04x} {:?}", marker, word, op_code),
+            Some((word, None)) => format!("{}{:04x} ???", marker, word),
+            None => format!("{}----", marker),
+        });
+        lines.push(String::new());
+
+        let registers = chip8.registers();
+        for row in 0..4u8 {
+            let line: Vec<String> = (row * 4..row * 4 + 4)
+                .map(|register| {
+                    let value = registers.get(VRegister::try_from(register).unwrap());
+                    format!("V{:X} {:02x}", register, value)
+                })
+                .collect();
+            lines.push(line.join("  "));
+        }
+
+        lines.push(format!(
+            "DT {:02x}  ST {:02x}",
+            chip8.delay_timer(),
+            chip8.sound_timer()
+        ));
+        lines.push(String::new());
+
+        lines.push("Stack".to_string());
+        lines.extend(address_rows(chip8.stack().iter().copied()));
+        lines.push(String::new());
+
+        lines.push("Breakpoints".to_string());
+        lines.extend(address_rows(self.breakpoints.iter().copied()));
+
+        if let Some(prompt) = &self.prompt {
+            lines.push(String::new());
+            lines.push(format!("Toggle breakpoint at: {}_", prompt));
+        }
+
+        for (row, line) in lines.iter().enumerate() {
+            engine.print(x, y + row as i32, line);
+        }
+    }
+
+    /// Steps the machine, returning whether the frame ended, and pauses on faults and watchpoints
+    fn execute(&mut self, chip8: &mut Chip8) -> Result<bool, Chip8Error> {
+        let result = chip8.step();
+
+        if result.is_err() || matches!(chip8.state(), ExecutionState::Watchpoint { .. }) {
+            self.pause();
+        }
+
+        result
+    }
+}
+
+/// Word at the program counter and the instruction it decodes to, if it is in memory
+fn next_instruction(chip8: &Chip8) -> Option<(u16, Option<OpCode>)> {
+    // Peeking doesn't trigger watchpoints, which are only meant for the program's own accesses
+    let address = chip8.program_counter();
+    let bytes = [
+        chip8.memory().peek(address).ok()?,
+        chip8.memory().peek(address.wrapping_add(1)).ok()?,
+    ];
+    Some((u16::from_be_bytes(bytes), OpCode::try_from(bytes).ok()))
+}
+
+/// Lays out addresses four per line
+fn address_rows(addresses: impl Iterator<Item = u16>) -> Vec<String> {
+    let addresses: Vec<String> = addresses
+        .map(|address| format!("{:#05x}", address))
+        .collect();
+
+    addresses.chunks(4).map(|row| row.join(" ")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chip8::{Headless, Quirks, Watchpoint};
+
+    /// Calls a subroutine setting V1 to 2 then stops, with the subroutine at 0x208
+    fn build_chip() -> Chip8 {
+        let mut chip8 = Chip8::new(Headless, Headless, Headless, Quirks::SCHIP);
+        chip8
+            .load(vec![
+                0x60, 0x01, // 0x200: LD V0, 1
+                0x22, 0x08, // 0x202: CALL 0x208
+                0x60, 0x03, // 0x204: LD V0, 3
+                0x12, 0x06, // 0x206: JP 0x206
+                0x61, 0x02, // 0x208: LD V1, 2
+                0x00, 0xEE, // 0x20A: RET
+            ])
+            .unwrap();
+        chip8
+    }
+
+    #[test]
+    fn starts_paused() {
+        let mut chip8 = build_chip();
+        let mut debugger = Debugger::new(&[]);
+        debugger.run_frame(&mut chip8).unwrap();
+        assert!(debugger.is_paused());
+        assert_eq!(chip8.program_counter(), 0x200);
+    }
+
+    #[test]
+    fn step_into_call() {
+        let mut chip8 = build_chip();
+        let mut debugger = Debugger::new(&[]);
+        debugger.step(&mut chip8).unwrap();
+        debugger.step(&mut chip8).unwrap();
+        assert_eq!(chip8.program_counter(), 0x208);
+        assert_eq!(chip8.stack(), &[0x204]);
+    }
+
+    #[test]
+    fn step_over_call() {
+        let mut chip8 = build_chip();
+        let mut debugger = Debugger::new(&[]);
+        debugger.step(&mut chip8).unwrap();
+        debugger.step_over(&mut chip8).unwrap();
+        debugger.run_frame(&mut chip8).unwrap();
+        assert!(debugger.is_paused());
+        assert_eq!(chip8.program_counter(), 0x204);
+        assert_eq!(chip8.registers().get(VRegister::V1), 2);
+    }
+
+    #[test]
+    fn stop_at_breakpoint() {
+        let mut chip8 = build_chip();
+        let mut debugger = Debugger::new(&[0x20A]);
+        debugger.resume(&mut chip8);
+        debugger.run_frame(&mut chip8).unwrap();
+        assert!(debugger.is_paused());
+        assert_eq!(chip8.program_counter(), 0x20A);
+
+        // Continuing goes past the breakpoint it stopped at
+        debugger.resume(&mut chip8);
+        debugger.run_frame(&mut chip8).unwrap();
+        assert_eq!(chip8.state(), &ExecutionState::Halted);
+    }
+
+    #[test]
+    fn toggle_breakpoint() {
+        let mut chip8 = build_chip();
+        let mut debugger = Debugger::new(&[0x204]);
+        debugger.toggle_breakpoint(0x204);
+        debugger.resume(&mut chip8);
+        debugger.run_frame(&mut chip8).unwrap();
+        assert_eq!(chip8.state(), &ExecutionState::Halted);
+    }
+
+    #[test]
+    fn pause_on_watchpoint() {
+        let mut chip8 = build_chip();
+        chip8.add_watchpoint(Watchpoint::RegisterChange(VRegister::V1));
+        let mut debugger = Debugger::new(&[]);
+        debugger.resume(&mut chip8);
+        debugger.run_frame(&mut chip8).unwrap();
+        assert!(debugger.is_paused());
+        assert_eq!(chip8.program_counter(), 0x20A);
+
+        // Stepping goes on from there
+        debugger.step(&mut chip8).unwrap();
+        assert_eq!(chip8.state(), &ExecutionState::Running);
+        assert_eq!(chip8.program_counter(), 0x204);
+    }
+
+    #[test]
+    fn pause_on_fault() {
+        let mut chip8 = Chip8::new(Headless, Headless, Headless, Quirks::SCHIP);
+        chip8.load(vec![0x00, 0xEE]).unwrap();
+        let mut debugger = Debugger::new(&[]);
+        debugger.resume(&mut chip8);
+        assert!(debugger.run_frame(&mut chip8).is_err());
+        assert!(debugger.is_paused());
+    }
+}
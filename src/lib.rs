@@ -0,0 +1,19 @@
+//! CHIP-8 interpreter core, with the SUPER-CHIP and XO-CHIP extensions
+//!
+//! Create a [`Chip8`] with the [`Quirks`] of the platform a ROM targets and the display, keypad
+//! and audio backends of the host, then `load` the ROM and call `run_frame` 60 times per second,
+//! `run` it as fast as possible, or drive it one instruction at a time with `run_instruction`. The
+//! machine state can be inspected at any point through the accessors on [`Chip8`].
+
+#[macro_use]
+extern crate log;
+
+mod chip8;
+
+pub use crate::chip8::{
+    Audio, Chip8, Chip8Error, Display, DrawInstruction, ExecutionState, FrameBuffer, Headless,
+    InputInstruction, InputKey, Keypad, Memory, MemoryAccess, OpCode, Platform, Quirks, Registers,
+    Sound, VRegister, Watchpoint, WatchpointHit, DEFAULT_INSTRUCTIONS_PER_FRAME,
+    DEFAULT_LOAD_ADDRESS, ETI_660_LOAD_ADDRESS, EXTENDED_MEMORY_SIZE, MEMORY_SIZE, PATTERN_LENGTH,
+    PLANE_COUNT,
+};
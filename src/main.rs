@@ -1,53 +1,265 @@
 #[macro_use]
 extern crate log;
 
-use console_engine::{pixel, Color, ConsoleEngine, KeyCode};
+use console_engine::{pixel, Color, KeyCode};
 
-mod chip8;
+use chip8::{Chip8, ExecutionState, Headless, Sound};
+use std::{
+    env, fs, process,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-use chip8::{Chip8, InputKey};
-use std::{fs::File, io::Read, thread};
+mod cli;
+mod debugger;
+
+use cli::{Options, USAGE};
+use debugger::Debugger;
+
+/// How far back the terminal frontend can rewind, 10 seconds
+const REWIND_FRAMES: usize = 10 * 60;
+
+/// How long a key stays down after its last press or repeat
+///
+/// Terminals don't report key releases, so this stands in for them. It has to outlast the delay
+/// before auto-repeat starts, or held keys would be released in between.
+const KEY_RELEASE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Colours for each combination of the two XO-CHIP planes, plane 0 being the lowest bit
+const PALETTE: [pixel::Pixel; 4] = [
+    pixel::Pixel {
+        fg: Color::Cyan,
+        bg: Color::Reset,
+        chr: ' ',
+    },
+    pixel::Pixel {
+        fg: Color::Cyan,
+        bg: Color::Reset,
+        chr: 'X',
+    },
+    pixel::Pixel {
+        fg: Color::Magenta,
+        bg: Color::Reset,
+        chr: 'X',
+    },
+    pixel::Pixel {
+        fg: Color::White,
+        bg: Color::Reset,
+        chr: 'X',
+    },
+];
 
 fn main() {
-    env_logger::init();
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, USAGE);
+            process::exit(2);
+        }
+    };
 
-    let (display_tx, display_rx) = std::sync::mpsc::channel();
-    let (input_tx, input_rx) = std::sync::mpsc::channel();
+    if options.help {
+        println!("{}", USAGE);
+        return;
+    }
 
-    let mut chip8 = Chip8::new(display_tx, input_rx);
+    let mut logger = env_logger::Builder::from_default_env();
+    if let Some(level) = options.log_level {
+        logger.filter_level(level);
+    }
+    logger.init();
 
-    let rom = File::open("./roms/random_number.ch8").expect("Unable to open file");
-    chip8.load(rom.bytes().map(|byte| byte.expect("Unable to read byte")));
+    let rom = match options.read_rom() {
+        Ok(rom) => rom,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    };
 
-    let screen_width = chip8.get_screen_width();
-    let screen_height = chip8.get_screen_height();
+    match options.headless_frames {
+        Some(frames) => run_headless(&options, rom, frames),
+        None => run_terminal(&options, rom),
+    }
+}
 
-    let mut engine =
-        console_engine::ConsoleEngine::init(screen_width as u32 + 2, screen_height as u32 + 10, 60);
-    let mut stopwatch = std::time::Instant::now();
-    let mut last_fps = 0;
+/// Applies the options shared by every mode and loads the ROM, exiting if it doesn't fit
+fn set_up(chip8: Chip8, options: &Options, rom: Vec<u8>) -> Chip8 {
+    let mut chip8 = match options.seed {
+        Some(seed) => chip8.with_seed(seed),
+        None => chip8,
+    };
 
-    let mut last_buffer = (*chip8.frame_buffer()).clone();
+    chip8.set_instructions_per_frame(options.instructions_per_frame);
+    chip8.set_load_address(options.load_address);
+    chip8.set_platform(options.platform);
 
-    thread::spawn(move || {
-        chip8.run();
-    });
+    for watchpoint in &options.watchpoints {
+        chip8.add_watchpoint(watchpoint.clone());
+    }
+
+    if let Err(error) = chip8.load(rom) {
+        eprintln!("Unable to load {}: {}", options.rom.display(), error);
+        process::exit(1);
+    }
+
+    chip8
+}
+
+/// Runs the given number of frames as fast as possible, then prints the screen and the state the
+/// machine ended up in
+fn run_headless(options: &Options, rom: Vec<u8>, frames: u64) {
+    let mut chip8 = set_up(
+        Chip8::new(Headless, Headless, Headless, options.quirks),
+        options,
+        rom,
+    );
+
+    let mut frame = 0;
+    while frame < frames && chip8.state().is_active() {
+        if let Err(error) = chip8.run_frame() {
+            error!("{}", error);
+        }
+
+        frame += 1;
+    }
+
+    let buffer = chip8.frame_buffer();
+    for y in 0..buffer.get_screen_height() {
+        let row: String = (0..buffer.get_screen_width())
+            .map(|x| if buffer.pixel(x, y) == 0 { '.' } else { '#' })
+            .collect();
+        println!("{}", row);
+    }
+
+    match chip8.state() {
+        ExecutionState::Faulted(error) => {
+            eprintln!("Fault after {} frames: {}", frame, error);
+            process::exit(1);
+        }
+        ExecutionState::Watchpoint { address, hit } => println!(
+            "Watchpoint at {:#05x} after {} frames: {}",
+            address, frame, hit
+        ),
+        state => println!("{:?} after {} frames", state, frame),
+    }
+}
+
+fn run_terminal(options: &Options, rom: Vec<u8>) {
+    let sound = Arc::new(Mutex::new(Sound::default()));
+
+    // Everything runs on this thread, so frames are taken straight from `run_frame` and keys are
+    // passed directly instead of going through display and keypad backends
+    let mut chip8 = set_up(
+        Chip8::new(Headless, Headless, Arc::clone(&sound), options.quirks),
+        options,
+        rom,
+    );
+    chip8.set_rpl_flags_path(options.rom.with_extension("rpl"));
+    chip8.set_rewind_capacity(REWIND_FRAMES);
+    let state_path = options.rom.with_extension("state");
+    let mut message = String::new();
+    let mut debugger = options.debug.then(|| Debugger::new(&options.breakpoints));
+    // When each mapped key was last pressed or repeated, while it is down
+    let mut key_events: Vec<Option<Instant>> = vec![None; options.key_map.len()];
+
+    // The debugger panel goes to the right of the screen
+    let panel_width = if debugger.is_some() {
+        debugger::PANEL_WIDTH
+    } else {
+        0
+    };
+
+    let mut screen_width = chip8.get_screen_width();
+    let mut screen_height = chip8.get_screen_height();
+
+    let mut engine = console_engine::ConsoleEngine::init(
+        screen_width as u32 + 2 + panel_width,
+        screen_height as u32 + 10,
+        60,
+    );
+    let mut stopwatch = std::time::Instant::now();
+    let mut last_fps = 0;
 
     loop {
         if engine.is_key_pressed(KeyCode::Char('q')) {
             break;
         }
 
-        if let Some(key) = get_current_key(&engine) {
-            input_tx.send(key.into()).unwrap();
+        engine.wait_frame();
+
+        if engine.is_key_pressed(KeyCode::F(5)) {
+            message = match fs::write(&state_path, chip8.save_state()) {
+                Ok(()) => "State saved".to_string(),
+                Err(error) => format!("Unable to save state: {}", error),
+            };
         }
 
-        engine.wait_frame();
+        if engine.is_key_pressed(KeyCode::F(9)) {
+            message = match fs::read(&state_path) {
+                Ok(state) => match chip8.load_state(&state) {
+                    Ok(()) => "State loaded".to_string(),
+                    Err(error) => format!("Unable to load state: {}", error),
+                },
+                Err(error) => format!("Unable to load state: {}", error),
+            };
+        }
+
+        let prompting = debugger.as_ref().is_some_and(Debugger::is_prompting);
+
+        let keys = options.key_map.iter().zip(&mut key_events);
+
+        for (&(character, key), last_event) in keys.filter(|_| !prompting) {
+            let code = KeyCode::Char(character);
+
+            // Held means the key was pressed or repeated again this frame
+            if engine.is_key_pressed(code) || engine.is_key_held(code) {
+                if last_event.is_none() {
+                    chip8.key_down(key);
+                }
+
+                *last_event = Some(Instant::now());
+            } else if last_event.is_some_and(|time| time.elapsed() >= KEY_RELEASE_TIMEOUT) {
+                chip8.key_up(key);
+                *last_event = None;
+            }
+        }
+
+        if let Some(debugger) = &mut debugger {
+            let result = debugger
+                .handle_keys(&engine, &mut chip8)
+                .and_then(|()| debugger.run_frame(&mut chip8));
+
+            if let Err(error) = result {
+                error!("{}", error);
+            }
+        } else if engine.is_key_held(KeyCode::Backspace) {
+            // Holding backspace plays the game backwards instead of running it
+            if let Err(error) = chip8.rewind(1) {
+                error!("Unable to rewind: {}", error);
+            }
+
+            message = format!("Rewind: {} frames left", chip8.rewindable_frames());
+        } else if let Err(error) = chip8.run_frame() {
+            // Faults are also shown below the screen, the machine stops executing after one
+            error!("{}", error);
+        }
+
+        // Only writes anything on the frames a program changed its flags
+        if let Err(error) = chip8.save_rpl_flags() {
+            message = format!("Unable to save RPL flags: {}", error);
+        }
 
-        let draw_instruction = display_rx.try_recv();
+        let buffer = chip8.frame_buffer();
 
-        if let Ok(draw_instruction) = draw_instruction {
-            last_buffer = draw_instruction.buffer().clone();
+        if buffer.get_screen_width() != screen_width || buffer.get_screen_height() != screen_height
+        {
+            screen_width = buffer.get_screen_width();
+            screen_height = buffer.get_screen_height();
+            engine.resize(
+                screen_width as u32 + 2 + panel_width,
+                screen_height as u32 + 10,
+            );
         }
 
         engine.clear_screen();
@@ -84,13 +296,10 @@ fn main() {
             pixel::pxl_fg('#', Color::Grey),
         );
 
-        for (y, row) in last_buffer.iter().enumerate() {
+        for y in 0..screen_height {
             for x in 0..screen_width {
-                if row >> (screen_width - x - 1) & 1 == 0 {
-                    engine.set_pxl(x as i32 + 1, y as i32 + 1, pixel::pxl_fg(' ', Color::Cyan));
-                } else {
-                    engine.set_pxl(x as i32 + 1, y as i32 + 1, pixel::pxl_fg('X', Color::Cyan));
-                }
+                let pixel = PALETTE[chip8.frame_buffer().pixel(x, y) as usize];
+                engine.set_pxl(x as i32 + 1, y as i32 + 1, pixel);
             }
         }
 
@@ -101,6 +310,38 @@ fn main() {
             format!("FPS: {}", last_fps).as_str(),
         );
 
+        if sound.lock().unwrap().is_active() {
+            engine.print(10, screen_height as i32 + 2, "BEEP");
+        }
+
+        match chip8.state() {
+            ExecutionState::Halted => engine.print(0, screen_height as i32 + 3, "Halted"),
+            ExecutionState::Faulted(error) => {
+                engine.print(0, screen_height as i32 + 3, &format!("Fault: {}", error))
+            }
+            ExecutionState::Watchpoint { address, hit } => engine.print(
+                0,
+                screen_height as i32 + 3,
+                &format!("Watchpoint at {:#05x}: {}", address, hit),
+            ),
+            _ => {}
+        }
+
+        engine.print(0, screen_height as i32 + 4, &message);
+        engine.print(
+            0,
+            screen_height as i32 + 5,
+            "F5: save state  F9: load state  Q: quit",
+        );
+
+        match &debugger {
+            Some(debugger) => {
+                debugger.draw_panel(&mut engine, &chip8, screen_width as i32 + 3, 0);
+                engine.print(0, screen_height as i32 + 6, debugger::HELP);
+            }
+            None => engine.print(0, screen_height as i32 + 6, "Backspace (hold): rewind"),
+        }
+
         engine.draw();
 
         if stopwatch.elapsed().as_millis() >= 1000 {
@@ -110,41 +351,3 @@ fn main() {
         }
     }
 }
-
-fn get_current_key(engine: &ConsoleEngine) -> Option<InputKey> {
-    if engine.is_key_pressed(KeyCode::Char('0')) {
-        Some(InputKey::Key0)
-    } else if engine.is_key_pressed(KeyCode::Char('1')) {
-        Some(InputKey::Key1)
-    } else if engine.is_key_pressed(KeyCode::Char('2')) {
-        Some(InputKey::Key2)
-    } else if engine.is_key_pressed(KeyCode::Char('3')) {
-        Some(InputKey::Key3)
-    } else if engine.is_key_pressed(KeyCode::Char('4')) {
-        Some(InputKey::Key4)
-    } else if engine.is_key_pressed(KeyCode::Char('5')) {
-        Some(InputKey::Key5)
-    } else if engine.is_key_pressed(KeyCode::Char('6')) {
-        Some(InputKey::Key6)
-    } else if engine.is_key_pressed(KeyCode::Char('7')) {
-        Some(InputKey::Key7)
-    } else if engine.is_key_pressed(KeyCode::Char('8')) {
-        Some(InputKey::Key8)
-    } else if engine.is_key_pressed(KeyCode::Char('9')) {
-        Some(InputKey::Key9)
-    } else if engine.is_key_pressed(KeyCode::Char('a')) {
-        Some(InputKey::KeyA)
-    } else if engine.is_key_pressed(KeyCode::Char('b')) {
-        Some(InputKey::KeyB)
-    } else if engine.is_key_pressed(KeyCode::Char('c')) {
-        Some(InputKey::KeyC)
-    } else if engine.is_key_pressed(KeyCode::Char('d')) {
-        Some(InputKey::KeyD)
-    } else if engine.is_key_pressed(KeyCode::Char('e')) {
-        Some(InputKey::KeyE)
-    } else if engine.is_key_pressed(KeyCode::Char('f')) {
-        Some(InputKey::KeyF)
-    } else {
-        None
-    }
-}